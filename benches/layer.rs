@@ -0,0 +1,19 @@
+//! benches/layer.rs
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use micrograd_rust::{layer::Layer, value::Value};
+
+fn bench_wide_layer(c: &mut Criterion) {
+    let layer = Layer::<f32>::new(64, 1000);
+    let inputs = Value::from_slice(&[0.5; 64]);
+
+    let mut group = c.benchmark_group("layer_1000_neurons");
+    group.bench_function("call", |b| b.iter(|| layer.call(black_box(&inputs))));
+    group.bench_function("par_call", |b| {
+        b.iter(|| layer.par_call(black_box(&inputs)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_wide_layer);
+criterion_main!(benches);
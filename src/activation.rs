@@ -0,0 +1,133 @@
+//! src/activation.rs
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    module::Module,
+    value::{cast, Scalar, Value},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Activation {
+    #[default]
+    Tanh,
+    Relu,
+    Sigmoid,
+    Linear,
+}
+
+impl Activation {
+    pub fn apply<T: Scalar>(&self, value: &Value<T>) -> Value<T> {
+        match self {
+            Activation::Tanh => value.tanh(),
+            Activation::Relu => value.relu(),
+            Activation::Sigmoid => value.sigmoid(),
+            Activation::Linear => value.clone(),
+        }
+    }
+}
+
+impl<T: Scalar> Module<T> for Activation {
+    fn call(&self, inputs: &[Value<T>]) -> Vec<Value<T>> {
+        inputs.iter().map(|x| self.apply(x)).collect()
+    }
+
+    fn parameters(&self) -> Vec<Value<T>> {
+        vec![]
+    }
+}
+
+/// Squashes values into `[lo, hi]` with a tanh, mapping `(-1, 1)` linearly
+/// onto the range. Put it after the last layer for bounded regression
+/// targets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScaledOutput<T = f32> {
+    lo: T,
+    hi: T,
+}
+
+impl<T: Scalar> ScaledOutput<T> {
+    pub fn new(lo: T, hi: T) -> ScaledOutput<T> {
+        assert!(
+            lo < hi,
+            "lower bound ({}) must be below upper bound ({})",
+            lo,
+            hi
+        );
+
+        ScaledOutput { lo, hi }
+    }
+
+    /// `lo + (tanh(x) + 1) * (hi - lo) / 2`
+    pub fn apply(&self, value: &Value<T>) -> Value<T> {
+        let half_range = (self.hi() - self.lo()) / cast(2.0);
+
+        &(&(&value.tanh() + T::one()) * half_range) + self.lo()
+    }
+
+    pub fn lo(&self) -> T {
+        self.lo
+    }
+
+    pub fn hi(&self) -> T {
+        self.hi
+    }
+}
+
+impl<T: Scalar> Module<T> for ScaledOutput<T> {
+    fn call(&self, inputs: &[Value<T>]) -> Vec<Value<T>> {
+        inputs.iter().map(|x| self.apply(x)).collect()
+    }
+
+    fn parameters(&self) -> Vec<Value<T>> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mlp::Mlp, module::Module, value::Value};
+
+    use super::{Activation, ScaledOutput};
+
+    #[test]
+    fn test_activation_apply() {
+        let a = &Value::new(-2.0_f32);
+
+        assert_eq!(Activation::Tanh.apply(a).data(), (-2.0_f32).tanh());
+        assert_eq!(Activation::Relu.apply(a).data(), 0.0);
+        assert_eq!(
+            Activation::Sigmoid.apply(a).data(),
+            1.0 / (1.0 + (2.0_f32).exp())
+        );
+        assert_eq!(Activation::Linear.apply(a).data(), -2.0);
+    }
+
+    #[test]
+    fn test_scaled_output_bounds() {
+        let scaled = ScaledOutput::new(10.0_f64, 20.0);
+
+        for x in [-1000.0, -1.0, 0.0, 0.5, 1000.0] {
+            let out = scaled.apply(&Value::new(x)).data();
+            assert!((10.0..=20.0).contains(&out), "{} -> {}", x, out);
+        }
+        assert_eq!(scaled.apply(&Value::new(0.0)).data(), 15.0);
+    }
+
+    #[test]
+    fn test_scaled_output_backward() {
+        let mlp = Mlp::<f64>::new_seeded(2, vec![3, 1], 7);
+        let scaled = ScaledOutput::new(-5.0, 5.0);
+
+        let x = Value::new(0.3);
+        let out = scaled.apply(&x);
+        out.backward();
+        // d/dx of 5 * tanh(x)
+        assert!((x.grad() - 5.0 * (1.0 - 0.3_f64.tanh().powi(2))).abs() < 1e-12);
+
+        let out = scaled.call(&mlp.callf(&vec![1.0, -1.0]));
+        out[0].backward();
+        assert!(mlp.parameters().iter().any(|p| p.grad() != 0.0));
+        assert!(Module::<f64>::parameters(&scaled).is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+//! src/batch_norm.rs
+
+use std::cell::{Cell, RefCell};
+
+use crate::{
+    functional::mean,
+    module::Module,
+    value::{cast, Scalar, Value},
+};
+
+/// Batch normalization over `num_features` inputs.
+///
+/// While training, each feature is shifted and scaled to zero mean and unit
+/// variance across the batch, then mapped through a learnable
+/// `gamma * x + beta`. The batch mean and variance are nodes in the graph, so
+/// every sample's gradient also flows through them into the rest of the
+/// batch. Running averages of the statistics are kept for inference.
+#[derive(Clone, Debug)]
+pub struct BatchNorm<T = f32> {
+    gamma: Vec<Value<T>>,
+    beta: Vec<Value<T>>,
+    momentum: T,
+    eps: T,
+    running_mean: RefCell<Vec<T>>,
+    running_var: RefCell<Vec<T>>,
+    training: Cell<bool>,
+}
+
+impl<T: Scalar> BatchNorm<T> {
+    pub fn new(num_features: usize) -> BatchNorm<T> {
+        BatchNorm::new_with_options(num_features, cast(0.1), cast(1e-5))
+    }
+
+    /// `momentum` is the weight of each new batch in the running statistics,
+    /// `eps` is added to the variance before taking its square root.
+    pub fn new_with_options(num_features: usize, momentum: T, eps: T) -> BatchNorm<T> {
+        BatchNorm {
+            gamma: (0..num_features).map(|_| Value::new(T::one())).collect(),
+            beta: (0..num_features).map(|_| Value::new(T::zero())).collect(),
+            momentum,
+            eps,
+            running_mean: RefCell::new(vec![T::zero(); num_features]),
+            running_var: RefCell::new(vec![T::one(); num_features]),
+            training: Cell::new(true),
+        }
+    }
+
+    /// Normalizes a batch of samples. While training this uses, and folds
+    /// into the running averages, the statistics of `batch` itself;
+    /// otherwise it uses the running averages.
+    pub fn call_batch(&self, batch: &[Vec<Value<T>>]) -> Vec<Vec<Value<T>>> {
+        batch.iter().for_each(|x| {
+            assert_eq!(
+                x.len(),
+                self.num_features(),
+                "num of inputs ({}) do not equal num of features ({})",
+                x.len(),
+                self.num_features()
+            )
+        });
+
+        if !self.training() {
+            return batch.iter().map(|x| self.call_running(x)).collect();
+        }
+
+        assert!(!batch.is_empty(), "batch norm of an empty batch");
+
+        let mut outputs: Vec<Vec<Value<T>>> =
+            vec![Vec::with_capacity(self.num_features()); batch.len()];
+        let mut running_mean = self.running_mean.borrow_mut();
+        let mut running_var = self.running_var.borrow_mut();
+
+        for j in 0..self.num_features() {
+            let column: Vec<Value<T>> = batch.iter().map(|x| x[j].clone()).collect();
+
+            let mu = mean(&column);
+            let centered: Vec<Value<T>> = column.iter().map(|x| x - &mu).collect();
+            let var = mean(&centered.iter().map(|c| c * c).collect::<Vec<Value<T>>>());
+            let std = (&var + self.eps).sqrt();
+
+            centered
+                .iter()
+                .zip(outputs.iter_mut())
+                .for_each(|(c, out)| {
+                    out.push(&(&(c / &std) * &self.gamma[j]) + &self.beta[j]);
+                });
+
+            // the running variance is the unbiased estimate, like PyTorch's
+            let n = batch.len();
+            let unbiased = if n > 1 {
+                var.data() * cast(n as f64 / (n - 1) as f64)
+            } else {
+                var.data()
+            };
+            let keep = T::one() - self.momentum();
+            running_mean[j] = keep * running_mean[j] + self.momentum() * mu.data();
+            running_var[j] = keep * running_var[j] + self.momentum() * unbiased;
+        }
+
+        outputs
+    }
+
+    fn call_running(&self, inputs: &[Value<T>]) -> Vec<Value<T>> {
+        let running_mean = self.running_mean.borrow();
+        let running_var = self.running_var.borrow();
+
+        inputs
+            .iter()
+            .enumerate()
+            .map(|(j, x)| {
+                let scale = (running_var[j] + self.eps()).sqrt().recip();
+
+                &(&(&(x - running_mean[j]) * scale) * &self.gamma[j]) + &self.beta[j]
+            })
+            .collect()
+    }
+
+    pub fn parameters(&self) -> Vec<Value<T>> {
+        [self.gamma(), self.beta()].concat()
+    }
+
+    pub fn num_features(&self) -> usize {
+        self.gamma.len()
+    }
+
+    pub fn gamma(&self) -> Vec<Value<T>> {
+        self.gamma.clone()
+    }
+
+    pub fn beta(&self) -> Vec<Value<T>> {
+        self.beta.clone()
+    }
+
+    pub fn momentum(&self) -> T {
+        self.momentum
+    }
+
+    pub fn eps(&self) -> T {
+        self.eps
+    }
+
+    pub fn running_mean(&self) -> Vec<T> {
+        self.running_mean.borrow().clone()
+    }
+
+    pub fn running_var(&self) -> Vec<T> {
+        self.running_var.borrow().clone()
+    }
+
+    /// Whether [`BatchNorm::call_batch`] uses batch statistics. Starts out
+    /// `true`.
+    pub fn training(&self) -> bool {
+        self.training.get()
+    }
+
+    pub fn set_training(&self, training: bool) {
+        self.training.set(training);
+    }
+}
+
+/// A single sample has no batch statistics to speak of, so this always uses
+/// the running averages. Train through [`BatchNorm::call_batch`].
+impl<T: Scalar> Module<T> for BatchNorm<T> {
+    fn call(&self, inputs: &[Value<T>]) -> Vec<Value<T>> {
+        assert_eq!(
+            inputs.len(),
+            self.num_features(),
+            "num of inputs ({}) do not equal num of features ({})",
+            inputs.len(),
+            self.num_features()
+        );
+
+        self.call_running(inputs)
+    }
+
+    fn parameters(&self) -> Vec<Value<T>> {
+        BatchNorm::parameters(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{module::Module, value::Value};
+
+    use super::BatchNorm;
+
+    fn batch() -> Vec<Vec<Value<f64>>> {
+        [[1.0, 100.0], [2.0, -50.0], [3.0, 20.0], [6.0, 10.0]]
+            .iter()
+            .map(|x| Value::from_slice(x))
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_norm_normalizes() {
+        let norm = BatchNorm::<f64>::new(2);
+        let outputs = norm.call_batch(&batch());
+
+        for j in 0..2 {
+            let column: Vec<f64> = outputs.iter().map(|o| o[j].data()).collect();
+            let mean = column.iter().sum::<f64>() / 4.0;
+            let var = column.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / 4.0;
+
+            assert!(mean.abs() < 1e-9, "feature {} mean {}", j, mean);
+            assert!((var - 1.0).abs() < 1e-3, "feature {} var {}", j, var);
+        }
+    }
+
+    #[test]
+    fn test_batch_norm_backward() {
+        let norm = BatchNorm::<f64>::new(2);
+        let inputs = batch();
+
+        let loss: Value<f64> = norm.call_batch(&inputs).into_iter().flatten().sum();
+        loss.backward();
+
+        // shifting a sample moves the batch mean with it, so a plain sum of
+        // the outputs can't be changed through any one input
+        for x in inputs.iter().flatten() {
+            assert!(x.grad().abs() < 1e-9, "input grad {}", x.grad());
+        }
+        assert!(norm.beta().iter().all(|b| b.grad() == 4.0));
+        assert!(norm.gamma().iter().all(|g| g.grad().abs() < 1e-9));
+        assert_eq!(norm.parameters().len(), 4);
+    }
+
+    #[test]
+    fn test_batch_norm_running_statistics() {
+        let norm = BatchNorm::<f64>::new_with_options(2, 1.0, 0.0);
+        norm.call_batch(&batch());
+
+        // mean 3 and unbiased variance 14 / 3 for the first feature
+        assert_eq!(norm.running_mean()[0], 3.0);
+        assert!((norm.running_var()[0] - 14.0 / 3.0).abs() < 1e-12);
+
+        norm.set_training(false);
+        let expected = (6.0 - 3.0) / (14.0_f64 / 3.0).sqrt();
+
+        let out = norm.call_batch(&[Value::from_slice(&[6.0, 0.0])]);
+        assert!((out[0][0].data() - expected).abs() < 1e-12);
+
+        let out = Module::call(&norm, &Value::from_slice(&[6.0, 0.0]));
+        assert!((out[0].data() - expected).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,321 @@
+//! src/data.rs
+
+use std::path::Path;
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::error::Error;
+
+/// Feature vectors and their scalar targets, aligned by index.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dataset {
+    xs: Vec<Vec<f32>>,
+    ys: Vec<f32>,
+}
+
+impl Dataset {
+    pub fn new(xs: Vec<Vec<f32>>, ys: Vec<f32>) -> Dataset {
+        assert_eq!(
+            xs.len(),
+            ys.len(),
+            "num of samples ({}) do not equal num of targets ({})",
+            xs.len(),
+            ys.len()
+        );
+
+        Dataset { xs, ys }
+    }
+
+    /// Reads a CSV with a header row. Column `target_column` becomes the
+    /// target and the remaining columns, in order, the features.
+    pub fn from_csv<P: AsRef<Path>>(path: P, target_column: usize) -> Result<Dataset, Error> {
+        let mut reader = csv::Reader::from_path(path)?;
+
+        let mut xs = vec![];
+        let mut ys = vec![];
+        for record in reader.records() {
+            let record = record?;
+            let line = record.position().map_or(0, |p| p.line());
+
+            let row = record
+                .iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    cell.trim().parse::<f32>().map_err(|_| Error::Parse {
+                        line,
+                        column,
+                        value: cell.to_string(),
+                    })
+                })
+                .collect::<Result<Vec<f32>, Error>>()?;
+
+            if target_column >= row.len() {
+                return Err(Error::MissingColumn {
+                    line,
+                    column: target_column,
+                });
+            }
+
+            let mut features = row;
+            ys.push(features.remove(target_column));
+            xs.push(features);
+        }
+
+        Ok(Dataset::new(xs, ys))
+    }
+
+    pub fn xs(&self) -> &Vec<Vec<f32>> {
+        &self.xs
+    }
+
+    pub fn ys(&self) -> &Vec<f32> {
+        &self.ys
+    }
+
+    pub fn len(&self) -> usize {
+        self.ys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ys.is_empty()
+    }
+}
+
+/// Shuffles the samples and splits them into a training set holding a
+/// `ratio` share of them (rounded) and a test set holding the rest.
+pub fn train_test_split(xs: &[Vec<f32>], ys: &[f32], ratio: f32, seed: u64) -> (Dataset, Dataset) {
+    assert!(
+        (0.0..=1.0).contains(&ratio),
+        "split ratio ({}) must be in [0, 1]",
+        ratio
+    );
+    assert_eq!(
+        xs.len(),
+        ys.len(),
+        "num of samples ({}) do not equal num of targets ({})",
+        xs.len(),
+        ys.len()
+    );
+
+    let mut order: Vec<usize> = (0..ys.len()).collect();
+    order.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    let n_train = (ratio * ys.len() as f32).round() as usize;
+    let subset = |indices: &[usize]| {
+        Dataset::new(
+            indices.iter().map(|&i| xs[i].clone()).collect(),
+            indices.iter().map(|&i| ys[i]).collect(),
+        )
+    };
+
+    (subset(&order[..n_train]), subset(&order[n_train..]))
+}
+
+/// Yields shuffled mini-batches of a [`Dataset`].
+///
+/// Each pass over the data is one epoch: the iterator returns `None` once
+/// every sample has been handed out, and the next call to `next` reshuffles
+/// and starts a new epoch. Loop over `loader.by_ref()` once per epoch.
+pub struct DataLoader<'a> {
+    dataset: &'a Dataset,
+    batch_size: usize,
+    drop_last: bool,
+    rng: StdRng,
+    order: Vec<usize>,
+    cursor: usize,
+}
+
+impl<'a> DataLoader<'a> {
+    pub fn new(dataset: &'a Dataset, batch_size: usize, drop_last: bool) -> DataLoader<'a> {
+        DataLoader::new_with_rng(dataset, batch_size, drop_last, StdRng::from_entropy())
+    }
+
+    /// Builds a loader whose batch order depends only on `seed`.
+    pub fn new_seeded(
+        dataset: &'a Dataset,
+        batch_size: usize,
+        drop_last: bool,
+        seed: u64,
+    ) -> DataLoader<'a> {
+        DataLoader::new_with_rng(dataset, batch_size, drop_last, StdRng::seed_from_u64(seed))
+    }
+
+    pub fn new_with_rng(
+        dataset: &'a Dataset,
+        batch_size: usize,
+        drop_last: bool,
+        rng: StdRng,
+    ) -> DataLoader<'a> {
+        assert!(batch_size > 0, "batch_size must be at least 1");
+
+        DataLoader {
+            dataset,
+            batch_size,
+            drop_last,
+            rng,
+            order: vec![],
+            cursor: 0,
+        }
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    pub fn drop_last(&self) -> bool {
+        self.drop_last
+    }
+}
+
+impl Iterator for DataLoader<'_> {
+    type Item = (Vec<Vec<f32>>, Vec<f32>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cursor == 0 {
+            self.order = (0..self.dataset.len()).collect();
+            self.order.shuffle(&mut self.rng);
+        }
+
+        let remaining = self.order.len() - self.cursor;
+        if remaining == 0 || (self.drop_last && remaining < self.batch_size) {
+            self.cursor = 0;
+            return None;
+        }
+
+        let end = self.cursor + remaining.min(self.batch_size);
+        let batch = &self.order[self.cursor..end];
+        self.cursor = end;
+
+        Some((
+            batch
+                .iter()
+                .map(|&i| self.dataset.xs()[i].clone())
+                .collect(),
+            batch.iter().map(|&i| self.dataset.ys()[i]).collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+
+    use super::{train_test_split, DataLoader, Dataset};
+
+    fn dataset(n: usize) -> Dataset {
+        let xs = (0..n).map(|i| vec![i as f32, -(i as f32)]).collect();
+        let ys = (0..n).map(|i| i as f32).collect();
+
+        Dataset::new(xs, ys)
+    }
+
+    #[test]
+    fn test_data_loader_epochs() {
+        let data = dataset(10);
+        let mut loader = DataLoader::new_seeded(&data, 4, false, 42);
+
+        let mut orders = vec![];
+        for _ in 0..2 {
+            let batches: Vec<_> = loader.by_ref().collect();
+            let sizes: Vec<usize> = batches.iter().map(|(xs, _)| xs.len()).collect();
+            assert_eq!(sizes, vec![4, 4, 2]);
+
+            let mut seen: Vec<f32> = vec![];
+            for (xs, ys) in batches {
+                // features stay attached to their targets
+                assert!(xs.iter().zip(&ys).all(|(x, y)| x[0] == *y));
+                seen.extend(ys);
+            }
+            orders.push(seen.clone());
+
+            seen.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert_eq!(seen, data.ys().clone());
+        }
+
+        assert_ne!(orders[0], orders[1], "each epoch is reshuffled");
+
+        // same seed, same batches
+        let again: Vec<f32> = DataLoader::new_seeded(&data, 4, false, 42)
+            .flat_map(|(_, ys)| ys)
+            .collect();
+        assert_eq!(again, orders[0]);
+    }
+
+    #[test]
+    fn test_data_loader_drop_last() {
+        let data = dataset(10);
+        let mut loader = DataLoader::new_seeded(&data, 4, true, 7);
+
+        for _ in 0..2 {
+            let sizes: Vec<usize> = loader.by_ref().map(|(_, ys)| ys.len()).collect();
+            assert_eq!(sizes, vec![4, 4]);
+        }
+
+        assert_eq!(DataLoader::new(&dataset(0), 4, false).count(), 0);
+    }
+
+    #[test]
+    fn test_train_test_split() {
+        let data = dataset(10);
+
+        let (train, test) = train_test_split(data.xs(), data.ys(), 0.8, 42);
+        assert_eq!((train.len(), test.len()), (8, 2));
+
+        let mut all: Vec<f32> = train.ys().iter().chain(test.ys()).copied().collect();
+        all.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            all,
+            data.ys().clone(),
+            "every sample lands on exactly one side"
+        );
+
+        for split in [&train, &test] {
+            assert!(split.xs().iter().zip(split.ys()).all(|(x, y)| x[0] == *y));
+        }
+
+        let (train, test) = train_test_split(data.xs(), data.ys(), 0.0, 42);
+        assert_eq!((train.len(), test.len()), (0, 10));
+
+        let (train, test) = train_test_split(data.xs(), data.ys(), 1.0, 42);
+        assert_eq!((train.len(), test.len()), (10, 0));
+    }
+
+    #[test]
+    fn test_dataset_from_csv() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/small.csv");
+        let data = Dataset::from_csv(path, 2).unwrap();
+
+        assert_eq!(data.len(), 3);
+        assert!(data.xs().iter().all(|x| x.len() == 3));
+        assert_eq!(data.xs()[1], vec![7.0, 3.2, 4.7]);
+        assert_eq!(data.ys().clone(), vec![0.0, 1.0, 2.0]);
+
+        assert!(matches!(
+            Dataset::from_csv(path, 4),
+            Err(Error::MissingColumn { line: 2, column: 4 })
+        ));
+    }
+
+    #[test]
+    fn test_dataset_from_csv_bad_cell() {
+        let path = std::env::temp_dir().join(format!("micrograd_bad_{}.csv", std::process::id()));
+        std::fs::write(&path, "a,b\n1.0,2.0\n3.0,oops\n").unwrap();
+        let result = Dataset::from_csv(&path, 1);
+        std::fs::remove_file(&path).unwrap();
+
+        let err = result.unwrap_err();
+        assert!(matches!(
+            &err,
+            Error::Parse { line: 3, column: 1, value } if value == "oops"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 1: could not parse \"oops\" as a number"
+        );
+
+        assert!(matches!(
+            Dataset::from_csv("does/not/exist.csv", 0),
+            Err(Error::Csv(_))
+        ));
+    }
+}
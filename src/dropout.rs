@@ -0,0 +1,139 @@
+//! src/dropout.rs
+
+use std::cell::{Cell, RefCell};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    module::Module,
+    value::{cast, Scalar, Value},
+};
+
+/// Inverted dropout: while training, each input is zeroed with probability
+/// `p` and the survivors are scaled by `1 / (1 - p)`. Inference is a no-op.
+#[derive(Clone, Debug)]
+pub struct Dropout {
+    p: f64,
+    rng: RefCell<StdRng>,
+    training: Cell<bool>,
+}
+
+impl Dropout {
+    pub fn new(p: f64) -> Dropout {
+        Dropout::new_with_rng(p, StdRng::from_entropy())
+    }
+
+    /// Builds a dropout whose masks depend only on `seed`.
+    pub fn new_seeded(p: f64, seed: u64) -> Dropout {
+        Dropout::new_with_rng(p, StdRng::seed_from_u64(seed))
+    }
+
+    pub fn new_with_rng(p: f64, rng: StdRng) -> Dropout {
+        assert!(
+            (0.0..1.0).contains(&p),
+            "dropout probability ({}) must be in [0, 1)",
+            p
+        );
+
+        Dropout {
+            p,
+            rng: RefCell::new(rng),
+            training: Cell::new(true),
+        }
+    }
+
+    /// The mask is multiplied in as a constant, so dropped inputs get no
+    /// gradient and kept inputs get theirs scaled by `1 / (1 - p)`.
+    pub fn call<T: Scalar>(&self, inputs: &[Value<T>], training: bool) -> Vec<Value<T>> {
+        if !training || self.p() == 0.0 {
+            return inputs.to_vec();
+        }
+
+        let scale = cast::<T>(1.0 / (1.0 - self.p()));
+        let mut rng = self.rng.borrow_mut();
+
+        inputs
+            .iter()
+            .map(|x| {
+                let mask = if rng.gen::<f64>() < self.p() {
+                    T::zero()
+                } else {
+                    scale
+                };
+
+                x * mask
+            })
+            .collect()
+    }
+
+    pub fn p(&self) -> f64 {
+        self.p
+    }
+
+    /// Whether [`Module::call`] applies the mask. Starts out `true`.
+    pub fn training(&self) -> bool {
+        self.training.get()
+    }
+
+    pub fn set_training(&self, training: bool) {
+        self.training.set(training);
+    }
+}
+
+impl<T: Scalar> Module<T> for Dropout {
+    fn call(&self, inputs: &[Value<T>]) -> Vec<Value<T>> {
+        Dropout::call(self, inputs, self.training())
+    }
+
+    fn parameters(&self) -> Vec<Value<T>> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::value::Value;
+
+    use super::Dropout;
+
+    #[test]
+    fn test_dropout_identity() {
+        let inputs = Value::from_slice(&[1.0_f32, -2.0, 3.0]);
+
+        let outputs = Dropout::new(0.0).call(&inputs, true);
+        assert!(outputs.iter().zip(&inputs).all(|(o, i)| o == i));
+
+        let outputs = Dropout::new(0.5).call(&inputs, false);
+        assert!(outputs.iter().zip(&inputs).all(|(o, i)| o == i));
+    }
+
+    #[test]
+    fn test_dropout_seeded_mask() {
+        let inputs = Value::from_slice(&[1.0_f32; 16]);
+        let dropout = Dropout::new_seeded(0.25, 42);
+
+        let outputs = dropout.call(&inputs, true);
+        let loss: Value = outputs.iter().cloned().sum();
+        loss.backward();
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let kept: Vec<bool> = (0..16).map(|_| rng.gen::<f64>() >= 0.25).collect();
+        assert!(
+            kept.iter().any(|k| !k),
+            "seed should drop at least one input"
+        );
+
+        outputs
+            .iter()
+            .zip(&inputs)
+            .zip(kept)
+            .for_each(|((o, i), kept)| {
+                let expected = if kept { 1.0 / 0.75 } else { 0.0 };
+
+                assert_eq!(o.data(), expected);
+                assert_eq!(i.grad(), expected);
+            });
+    }
+}
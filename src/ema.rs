@@ -0,0 +1,102 @@
+//! src/ema.rs
+
+use std::cell::RefCell;
+
+use crate::mlp::Mlp;
+
+/// Exponential moving average of a network's parameters, kept as a shadow
+/// copy aligned with [`Mlp::parameters`]. Call [`Ema::update`] after every
+/// optimizer step and [`Ema::apply_to`] before evaluating.
+#[derive(Clone, Debug)]
+pub struct Ema {
+    decay: f32,
+    shadow: RefCell<Vec<f32>>,
+}
+
+impl Ema {
+    /// Starts the average at the current parameters of `mlp`.
+    pub fn new(mlp: &Mlp, decay: f32) -> Ema {
+        assert!(
+            (0.0..=1.0).contains(&decay),
+            "ema decay ({}) must be in [0, 1]",
+            decay
+        );
+
+        Ema {
+            decay,
+            shadow: RefCell::new(mlp.get_parameters_flat()),
+        }
+    }
+
+    /// `shadow = decay * shadow + (1 - decay) * param` for every parameter.
+    pub fn update(&self, mlp: &Mlp) {
+        let mut shadow = self.shadow.borrow_mut();
+        assert_eq!(
+            mlp.parameter_count(),
+            shadow.len(),
+            "num of parameters ({}) do not equal num of averaged values ({})",
+            mlp.parameter_count(),
+            shadow.len()
+        );
+
+        shadow
+            .iter_mut()
+            .zip(mlp.parameters_iter())
+            .for_each(|(s, p)| *s = self.decay() * *s + (1.0 - self.decay()) * p.data());
+    }
+
+    /// Overwrites the parameters of `mlp` with the averaged values. Keep a
+    /// [`Mlp::get_parameters_flat`] copy first to resume training from the
+    /// raw weights.
+    pub fn apply_to(&self, mlp: &Mlp) {
+        mlp.set_parameters_from(&self.shadow.borrow());
+    }
+
+    pub fn decay(&self) -> f32 {
+        self.decay
+    }
+
+    pub fn shadow(&self) -> Vec<f32> {
+        self.shadow.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mlp::Mlp;
+
+    use super::Ema;
+
+    #[test]
+    fn test_ema_lags_parameters() {
+        let mlp = Mlp::new(1, vec![1]);
+        mlp.set_parameters_from(&[0.0, 0.0]);
+        let ema = Ema::new(&mlp, 0.5);
+
+        // the raw weight jumps to 1 and stays there, the average closes half
+        // the remaining gap each step
+        mlp.set_parameters_from(&[1.0, -1.0]);
+        for expected in [0.5, 0.75, 0.875] {
+            ema.update(&mlp);
+            assert_eq!(ema.shadow(), vec![expected, -expected]);
+        }
+
+        ema.apply_to(&mlp);
+        assert_eq!(mlp.get_parameters_flat(), vec![0.875, -0.875]);
+    }
+
+    #[test]
+    fn test_ema_decay_extremes() {
+        let mlp = Mlp::new(2, vec![2]);
+        let start = mlp.get_parameters_flat();
+
+        let frozen = Ema::new(&mlp, 1.0);
+        let tracking = Ema::new(&mlp, 0.0);
+        mlp.reinit(Some(1));
+        frozen.update(&mlp);
+        tracking.update(&mlp);
+
+        assert_eq!(frozen.shadow(), start);
+        assert_eq!(tracking.shadow(), mlp.get_parameters_flat());
+    }
+}
@@ -0,0 +1,113 @@
+//! src/error.rs
+
+use std::{fmt, io};
+
+/// Errors from loading data into the crate or feeding it to a model.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Csv(csv::Error),
+    Json(serde_json::Error),
+    /// A cell that isn't a number. `line` is 1-based and counts the header.
+    Parse {
+        line: u64,
+        column: usize,
+        value: String,
+    },
+    /// A row too short to contain the requested target column.
+    MissingColumn {
+        line: u64,
+        column: usize,
+    },
+    /// An input of the wrong length for the model it was passed to.
+    InputSize {
+        expected: usize,
+        found: usize,
+    },
+    /// A flat parameter list that doesn't fit the architecture it came with.
+    ParameterCount {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "io error: {}", err),
+            Error::Csv(err) => write!(f, "csv error: {}", err),
+            Error::Json(err) => write!(f, "json error: {}", err),
+            Error::Parse {
+                line,
+                column,
+                value,
+            } => write!(
+                f,
+                "line {}, column {}: could not parse {:?} as a number",
+                line, column, value
+            ),
+            Error::MissingColumn { line, column } => {
+                write!(f, "line {}: no column {}", line, column)
+            }
+            Error::InputSize { expected, found } => {
+                write!(f, "expected {} inputs, found {}", expected, found)
+            }
+            Error::ParameterCount { expected, found } => write!(
+                f,
+                "expected {} parameters for the architecture, found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+/// A graph node holding a NaN or infinite `data` or `grad`, see
+/// [`crate::value::Value::check_finite`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct NonFiniteError {
+    pub id: usize,
+    pub op: String,
+    pub data: f64,
+    pub grad: f64,
+}
+
+impl fmt::Display for NonFiniteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "node {} ({:?}) is not finite: data={}, grad={}",
+            self.id, self.op, self.data, self.grad
+        )
+    }
+}
+
+impl std::error::Error for NonFiniteError {}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Csv(err) => Some(err),
+            Error::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(err: csv::Error) -> Error {
+        Error::Csv(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::Json(err)
+    }
+}
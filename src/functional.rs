@@ -0,0 +1,189 @@
+//! src/functional.rs
+
+use crate::value::{cast, Op, Scalar, Value};
+
+/// Sum of `values` as a single node, passing its gradient unchanged to every
+/// input. Zero for an empty slice.
+pub fn sum<T: Scalar>(values: &[Value<T>]) -> Value<T> {
+    let data = values.iter().fold(T::zero(), |acc, v| acc + v.data());
+
+    Value::with_backward(data, values.to_vec(), "sum", Op::Add)
+}
+
+/// Mean of `values` as a single node, giving each input `1 / n` of its
+/// gradient.
+pub fn mean<T: Scalar>(values: &[Value<T>]) -> Value<T> {
+    assert!(!values.is_empty(), "mean of an empty slice");
+
+    let total = values.iter().fold(T::zero(), |acc, v| acc + v.data());
+    let data = total / cast(values.len() as f64);
+
+    Value::with_backward(data, values.to_vec(), "mean", Op::Mean)
+}
+
+/// Largest of `values` as a single node, like max pooling. The whole
+/// gradient goes to the largest input, the first one on ties.
+pub fn max_reduce<T: Scalar>(values: &[Value<T>]) -> Value<T> {
+    assert!(!values.is_empty(), "max of an empty slice");
+
+    let data = values
+        .iter()
+        .map(|v| v.data())
+        .fold(T::neg_infinity(), T::max);
+
+    Value::with_backward(data, values.to_vec(), "max_reduce", Op::MaxReduce)
+}
+
+/// Softmax over a slice of logits.
+///
+/// The largest logit is subtracted before exponentiating so large inputs
+/// don't overflow. It enters the graph as a plain constant, so the shift
+/// adds no gradient paths of its own.
+pub fn softmax<T: Scalar>(logits: &[Value<T>]) -> Vec<Value<T>> {
+    let max = logits
+        .iter()
+        .map(|l| l.data())
+        .fold(T::neg_infinity(), T::max);
+
+    let exps: Vec<Value<T>> = logits.iter().map(|l| (l - max).exp()).collect();
+    let sum = &exps.iter().cloned().sum::<Value<T>>();
+
+    exps.iter().map(|e| e / sum).collect()
+}
+
+/// `d outputs[i] / d inputs[j]` as row `i`, column `j`, from one backward
+/// pass per output. Every grad in the outputs' graphs is overwritten along
+/// the way; inputs an output doesn't depend on get a zero.
+pub fn jacobian<T: Scalar>(outputs: &[Value<T>], inputs: &[Value<T>]) -> Vec<Vec<T>> {
+    outputs
+        .iter()
+        .map(|output| {
+            output.topo().iter().for_each(|v| v.zero_grad());
+            inputs.iter().for_each(|x| x.zero_grad());
+
+            output.backward();
+
+            inputs.iter().map(|x| x.grad()).collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::{Value, Value64};
+
+    use super::{jacobian, max_reduce, mean, softmax, sum};
+
+    #[test]
+    fn test_jacobian() {
+        let x = Value::new(2.0_f64);
+        let y = Value::new(5.0_f64);
+        let z = Value::new(1.0_f64);
+
+        let outputs = [&x * &y, &x + &y, x.powf(3.0)];
+        let j = jacobian(&outputs, &[x.clone(), y.clone(), z]);
+
+        // rows: d(xy) = [y, x], d(x + y) = [1, 1], d(x^3) = [3x^2, 0]; z is unused
+        assert_eq!(
+            j,
+            vec![
+                vec![5.0, 2.0, 0.0],
+                vec![1.0, 1.0, 0.0],
+                vec![12.0, 0.0, 0.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sum() {
+        let values = Value::from_slice(&[1.0_f32, -2.0, 4.0]);
+
+        let total = sum(&values);
+        total.backward();
+
+        assert_eq!(total.data(), 3.0);
+        assert_eq!(total.op(), String::from("sum"));
+        assert_eq!(total.graph_size(), 4, "one node over the inputs");
+        assert!(values.iter().all(|v| v.grad() == 1.0));
+
+        assert_eq!(sum::<f32>(&[]).data(), 0.0);
+    }
+
+    #[test]
+    fn test_mean() {
+        let values = Value::from_slice(&[1.0_f32, -2.0, 4.0, 1.0]);
+
+        let avg = mean(&values);
+        avg.set_grad(2.0);
+        avg.once_backward();
+
+        assert_eq!(avg.data(), 1.0);
+        assert!(values.iter().all(|v| v.grad() == 0.5));
+    }
+
+    #[test]
+    fn test_max_reduce() {
+        let values = Value::from_slice(&[1.0_f32, 5.0, -2.0, 5.0]);
+
+        let max = max_reduce(&values);
+        let loss = &max * 3.0;
+        loss.backward();
+
+        assert_eq!(max.data(), 5.0);
+        let grads: Vec<f32> = values.iter().map(|v| v.grad()).collect();
+        assert_eq!(grads, vec![0.0, 3.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_softmax_sums_to_one() {
+        let logits = [Value::new(1.0_f32), Value::new(2.0), Value::new(100.0)];
+
+        let probs = softmax(&logits);
+        let total: f32 = probs.iter().map(|p| p.data()).sum();
+
+        assert!((total - 1.0).abs() < 1e-6, "softmax total {}", total);
+        assert!(probs.iter().all(|p| p.data().is_finite()));
+    }
+
+    #[test]
+    fn test_softmax_backward() {
+        let weights = [1.0, 2.0, 3.0];
+        let f = |logits: &[f64]| -> f64 {
+            let values: Vec<Value64> = logits.iter().map(|l| Value::new(*l)).collect();
+
+            softmax(&values)
+                .iter()
+                .zip(weights)
+                .map(|(p, w)| p.data() * w)
+                .sum()
+        };
+
+        let inputs = [0.5, -1.0, 2.0];
+        let logits: Vec<Value64> = inputs.iter().map(|l| Value::new(*l)).collect();
+
+        let out: Value64 = softmax(&logits)
+            .iter()
+            .zip(weights)
+            .map(|(p, w)| p * w)
+            .sum();
+        out.backward();
+
+        let eps = 1e-6;
+        for (i, logit) in logits.iter().enumerate() {
+            let mut plus = inputs;
+            let mut minus = inputs;
+            plus[i] += eps;
+            minus[i] -= eps;
+
+            let numeric = (f(&plus) - f(&minus)) / (2.0 * eps);
+
+            assert!(
+                (logit.grad() - numeric).abs() < 1e-6,
+                "logit {} grad {} vs {}",
+                i,
+                logit.grad(),
+                numeric
+            );
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! src/init.rs
+
+use rand::{distributions::Uniform, prelude::Distribution, Rng};
+use rand_distr::Normal;
+
+/// How a neuron's weights and bias are sampled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Init {
+    /// Weights and bias from `U(-1, 1)`, regardless of fan-in.
+    #[default]
+    Uniform,
+    /// Weights from `N(0, 2 / nin)`, suited to ReLU layers. Bias starts at zero.
+    Kaiming,
+    /// Weights from `N(0, 1 / nin)`, suited to tanh layers. Bias starts at zero.
+    Xavier,
+}
+
+impl Init {
+    /// Samples `nin` weights followed by a bias.
+    pub fn sample<R: Rng + ?Sized>(&self, nin: usize, rng: &mut R) -> (Vec<f64>, f64) {
+        match self {
+            Init::Uniform => {
+                let uniform = Uniform::new_inclusive(-1.0, 1.0);
+
+                let weights = (0..nin).map(|_| uniform.sample(rng)).collect();
+                let bias = uniform.sample(rng);
+
+                (weights, bias)
+            }
+            Init::Kaiming => (Init::normal(2.0, nin, rng), 0.0),
+            Init::Xavier => (Init::normal(1.0, nin, rng), 0.0),
+        }
+    }
+
+    fn normal<R: Rng + ?Sized>(gain: f64, nin: usize, rng: &mut R) -> Vec<f64> {
+        let std = (gain / nin.max(1) as f64).sqrt();
+        let normal = Normal::new(0.0, std).expect("standard deviation is finite");
+
+        (0..nin).map(|_| normal.sample(rng)).collect()
+    }
+}
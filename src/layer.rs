@@ -1,41 +1,258 @@
 //! src/layer.rs
 
-use crate::{neuron::Neuron, value::Value};
+use rand::Rng;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
-pub struct Layer(Vec<Neuron>);
+use crate::{
+    activation::Activation,
+    init::Init,
+    module::Module,
+    neuron::Neuron,
+    value::{cast, Scalar, Value},
+};
 
-impl Layer {
-    pub fn new(nin: usize, nout: usize) -> Layer {
-        let neurons = (0..nout).map(|_| Neuron::new(nin)).collect();
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Scalar + Serialize",
+    deserialize = "T: Scalar + Deserialize<'de>"
+))]
+pub struct Layer<T = f32>(Vec<Neuron<T>>);
 
+impl<T: Scalar> Layer<T> {
+    pub fn new(nin: usize, nout: usize) -> Layer<T> {
+        Layer::new_with_init(nin, nout, Init::Uniform)
+    }
+
+    /// A layer without an activation, producing raw pre-activation outputs.
+    pub fn new_linear(nin: usize, nout: usize) -> Layer<T> {
+        Layer::new_with_activation(nin, nout, Activation::Linear)
+    }
+
+    /// A layer whose neurons all use `activation`.
+    pub fn new_with_activation(nin: usize, nout: usize, activation: Activation) -> Layer<T> {
+        Layer::new_with_rng(
+            nin,
+            nout,
+            activation,
+            Init::Uniform,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    pub fn new_with_init(nin: usize, nout: usize, init: Init) -> Layer<T> {
+        Layer::new_with_rng(nin, nout, Activation::Tanh, init, &mut rand::thread_rng())
+    }
+
+    pub fn new_with_rng<R: Rng + ?Sized>(
+        nin: usize,
+        nout: usize,
+        activation: Activation,
+        init: Init,
+        rng: &mut R,
+    ) -> Layer<T> {
+        let neurons = (0..nout)
+            .map(|_| Neuron::new_with_rng(nin, activation, init, rng))
+            .collect();
+
+        Layer(neurons)
+    }
+
+    /// A layer over existing neurons, e.g. from [`Neuron::from_parameters`].
+    pub fn from_neurons(neurons: Vec<Neuron<T>>) -> Layer<T> {
         Layer(neurons)
     }
 
-    pub fn call(&self, inputs: &Vec<Value>) -> Vec<Value> {
+    #[allow(clippy::ptr_arg)]
+    pub fn call(&self, inputs: &Vec<Value<T>>) -> Vec<Value<T>> {
         self.neurons().iter().map(|n| n.call(inputs)).collect()
     }
 
-    pub fn parameters(&self) -> Vec<Value> {
+    pub fn parameters(&self) -> Vec<Value<T>> {
+        self.parameters_iter().collect()
+    }
+
+    /// Lazy version of [`Layer::parameters`], without the per-neuron vectors.
+    pub fn parameters_iter(&self) -> impl Iterator<Item = Value<T>> + '_ {
+        self.neurons().iter().flat_map(|n| n.parameters_iter())
+    }
+
+    pub fn parameter_count(&self) -> usize {
+        self.neurons().iter().map(|n| n.parameter_count()).sum()
+    }
+
+    pub fn zero_grad(&self) {
+        self.parameters_iter().for_each(|p| p.zero_grad());
+    }
+
+    /// L2 norm of this layer's parameter gradients.
+    pub fn grad_norm(&self) -> T {
+        self.parameters()
+            .iter()
+            .fold(T::zero(), |acc, p| acc + p.grad() * p.grad())
+            .sqrt()
+    }
+
+    /// Renders `|grad|` of every weight as shade characters, one line per
+    /// neuron and one column per input, scaled so the largest magnitude in
+    /// the layer is `█`. Biases are left out.
+    pub fn grad_heatmap(&self) -> String {
+        const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+        let grads: Vec<Vec<T>> = self
+            .neurons()
+            .iter()
+            .map(|n| n.weights().iter().map(|w| w.grad().abs()).collect())
+            .collect();
+        let max = grads.iter().flatten().fold(T::zero(), |acc, g| acc.max(*g));
+
+        grads
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|g| {
+                        if max == T::zero() {
+                            return SHADES[0];
+                        }
+
+                        let level = (*g / max * cast((SHADES.len() - 1) as f64)).round();
+                        SHADES[level.to_usize().unwrap_or(0)]
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Neurons whose weights and bias all have a zero gradient, e.g. ReLU
+    /// units that never fire. Only meaningful after a backward pass.
+    pub fn dead_neuron_count(&self) -> usize {
         self.neurons()
             .iter()
-            .map(|n| n.parameters())
-            .collect::<Vec<Vec<Value>>>()
-            .concat()
+            .filter(|n| n.parameters_iter().all(|p| p.grad() == T::zero()))
+            .count()
+    }
+
+    /// Number of inputs each neuron expects, 0 for an empty layer.
+    pub fn nin(&self) -> usize {
+        self.neurons().first().map_or(0, |n| n.num_weights())
     }
 
-    pub fn neurons(&self) -> &Vec<Neuron> {
+    pub fn nout(&self) -> usize {
+        self.neurons().len()
+    }
+
+    /// Sets the activation of every neuron in the layer.
+    pub fn set_activation(&self, activation: Activation) {
+        self.neurons()
+            .iter()
+            .for_each(|n| n.set_activation(activation));
+    }
+
+    pub fn neurons(&self) -> &Vec<Neuron<T>> {
         &self.0
     }
 
-    pub fn set_neurons(&mut self, neurons: Vec<Neuron>) {
+    pub fn set_neurons(&mut self, neurons: Vec<Neuron<T>>) {
         self.0 = neurons;
     }
 }
 
+#[cfg(feature = "rayon")]
+impl<T: Scalar + Send + Sync> Layer<T> {
+    /// Parallel version of [`Layer::call`].
+    ///
+    /// This doesn't need the `threadsafe` feature. Without it graph nodes are
+    /// `Rc`/`RefCell` and can't cross threads, and with it they could but
+    /// every access would take a lock. Either way the weights and inputs are
+    /// snapshotted as plain numbers, each neuron's output and activation
+    /// slope is computed on the rayon pool, and the results are stitched back
+    /// into the graph on the calling thread as one fused `"neuron"` node per
+    /// neuron.
+    ///
+    /// That node's backward is a closure over plain numbers, so the output
+    /// only supports first-order [`Value::backward`], where it hands out the
+    /// same gradients the unfused graph from [`Layer::call`] would.
+    /// [`Value::gradients`] panics on any graph built from it.
+    pub fn par_call(&self, inputs: &[Value<T>]) -> Vec<Value<T>> {
+        let xs: Vec<T> = inputs.iter().map(|x| x.data()).collect();
+        let snapshot: Vec<(Vec<T>, T, Activation)> = self
+            .neurons()
+            .iter()
+            .map(|n| {
+                assert_eq!(
+                    n.num_weights(),
+                    inputs.len(),
+                    "num of inputs ({}) do not equal num of weights ({})",
+                    inputs.len(),
+                    n.num_weights()
+                );
+
+                let weights = n.weights().iter().map(|w| w.data()).collect();
+                (weights, n.bias().data(), n.activation())
+            })
+            .collect();
+
+        // the throwaway values built here never leave their worker thread
+        let outputs: Vec<(T, T)> = snapshot
+            .par_iter()
+            .map(|(weights, bias, activation)| {
+                let z = xs
+                    .iter()
+                    .zip(weights)
+                    .fold(T::zero(), |acc, (x, w)| acc + *x * *w)
+                    + *bias;
+
+                let z = Value::new(z);
+                let out = activation.apply(&z);
+                out.backward();
+
+                (out.data(), z.grad())
+            })
+            .collect();
+
+        self.neurons()
+            .iter()
+            .zip(outputs)
+            .map(|(n, (data, slope))| {
+                let weights = n.weights();
+                let bias = n.bias();
+                let children = [weights.clone(), vec![bias.clone()], inputs.to_vec()].concat();
+
+                let value = Value::with_op(data, Some(children), "neuron");
+
+                let v = value.downgrade();
+                let inputs = inputs.to_vec();
+                value.set_backward(move || {
+                    let grad = slope * v.grad();
+
+                    weights.iter().zip(&inputs).for_each(|(w, x)| {
+                        w.accumulate_grad(x.data() * grad);
+                        x.accumulate_grad(w.data() * grad);
+                    });
+                    bias.accumulate_grad(grad);
+                });
+
+                value
+            })
+            .collect()
+    }
+}
+
+impl<T: Scalar> Module<T> for Layer<T> {
+    fn call(&self, inputs: &[Value<T>]) -> Vec<Value<T>> {
+        Layer::call(self, &inputs.to_vec())
+    }
+
+    fn parameters(&self) -> Vec<Value<T>> {
+        Layer::parameters(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::value::Value;
+    use crate::{activation::Activation, neuron::Neuron, value::Value};
 
     use super::Layer;
 
@@ -53,4 +270,122 @@ mod tests {
 
         assert_eq!(out.len(), 3);
     }
+
+    #[test]
+    fn test_layer_from_neurons() {
+        let layer = Layer::from_neurons(vec![
+            Neuron::from_parameters(vec![1.0_f64, 2.0], 0.5),
+            Neuron::from_parameters(vec![-1.0, 0.0], 0.0),
+        ]);
+        layer.set_activation(Activation::Linear);
+
+        assert_eq!((layer.nin(), layer.nout()), (2, 2));
+
+        let out: Vec<f64> = layer
+            .call(&Value::from_slice(&[3.0, 1.0]))
+            .iter()
+            .map(|o| o.data())
+            .collect();
+        assert_eq!(out, vec![5.5, -3.0]);
+    }
+
+    #[test]
+    fn test_layer_grad_heatmap() {
+        let layer = Layer::<f64>::new(3, 2);
+
+        let heatmap = layer.grad_heatmap();
+        let lines: Vec<&str> = heatmap.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l == &"   "));
+
+        let grads = [[4.0, -2.0, 0.0], [1.0, 0.0, -4.0]];
+        layer.neurons().iter().zip(grads).for_each(|(n, row)| {
+            n.weights().iter().zip(row).for_each(|(w, g)| w.set_grad(g));
+        });
+
+        let heatmap = layer.grad_heatmap();
+
+        assert_eq!(heatmap, "█▒ \n░ █");
+        assert!(heatmap.lines().all(|l| l.chars().count() == layer.nin()));
+    }
+
+    #[test]
+    fn test_layer_dead_neuron_count() {
+        let layer = Layer::<f64>::new_with_activation(2, 3, Activation::Relu);
+        layer.neurons().iter().for_each(|n| {
+            n.set_weights(Value::from_slice(&[1.0, 1.0]));
+            n.set_bias(Value::new(0.0));
+        });
+
+        // a pre-activation below zero for any positive input keeps it off
+        let dead = &layer.neurons()[1];
+        dead.set_weights(Value::from_slice(&[-1.0, -1.0]));
+        dead.set_bias(Value::new(-5.0));
+
+        for x in [[1.0, 2.0], [0.5, 3.0]] {
+            let loss: Value<f64> = layer.call(&Value::from_slice(&x)).into_iter().sum();
+            loss.backward();
+        }
+
+        assert_eq!(layer.dead_neuron_count(), 1);
+
+        layer.zero_grad();
+        assert_eq!(layer.dead_neuron_count(), 3);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_layer_par_call_matches_call() {
+        let mut layer = Layer::<f64>::new(3, 8);
+        let mut neurons = layer.neurons().clone();
+        neurons[1] = Neuron::new_with_activation(3, Activation::Relu);
+        neurons[2] = Neuron::new_with_activation(3, Activation::Sigmoid);
+        neurons[3] = Neuron::new_with_activation(3, Activation::Linear);
+        layer.set_neurons(neurons);
+
+        let run = |par: bool| {
+            let inputs = Value::from_slice(&[2.0, -1.0, 0.5]);
+            layer.zero_grad();
+
+            let outs = if par {
+                layer.par_call(&inputs)
+            } else {
+                layer.call(&inputs)
+            };
+            let loss: Value<f64> = outs.iter().map(|o| o * o).sum();
+            loss.backward();
+
+            let data: Vec<f64> = outs.iter().map(|o| o.data()).collect();
+            let grads: Vec<f64> = layer
+                .parameters()
+                .iter()
+                .chain(&inputs)
+                .map(|p| p.grad())
+                .collect();
+
+            (data, grads)
+        };
+
+        let (seq_data, seq_grads) = run(false);
+        let (par_data, par_grads) = run(true);
+
+        assert_eq!(seq_data, par_data);
+        seq_grads
+            .iter()
+            .zip(&par_grads)
+            .for_each(|(s, p)| assert!((s - p).abs() < 1e-12, "{} vs {}", s, p));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    #[should_panic(
+        expected = "gradients can't differentiate through the \"neuron\" node, its custom backward only supports backward()"
+    )]
+    fn test_layer_par_call_first_order_only() {
+        let layer = Layer::<f64>::new(3, 2);
+        let inputs = Value::from_slice(&[2.0, -1.0, 0.5]);
+
+        let loss: Value<f64> = layer.par_call(&inputs).into_iter().sum();
+        loss.gradients(&inputs);
+    }
 }
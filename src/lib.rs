@@ -1,6 +1,24 @@
 //! src/value.rs
 
+pub mod activation;
+pub mod batch_norm;
+pub mod data;
+pub mod dropout;
+pub mod ema;
+pub mod error;
+pub mod functional;
+pub mod init;
 pub mod layer;
+pub mod loss;
+pub mod metrics;
 pub mod mlp;
+pub mod module;
 pub mod neuron;
+pub mod optim;
+pub mod prelude;
+pub mod scheduler;
+mod shared;
+#[cfg(any(test, feature = "testutil"))]
+pub mod testutil;
+pub mod trainer;
 pub mod value;
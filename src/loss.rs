@@ -0,0 +1,525 @@
+//! src/loss.rs
+
+use crate::{
+    functional::{mean, sum},
+    value::{cast, Scalar, Value},
+};
+
+/// How the per-sample losses of a batch are combined, as in PyTorch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reduction {
+    #[default]
+    Mean,
+    Sum,
+    /// Keep one loss per sample, e.g. to weight them by hand.
+    None,
+}
+
+impl Reduction {
+    pub fn apply<T: Scalar>(&self, losses: Vec<Value<T>>) -> Reduced<T> {
+        match self {
+            Reduction::Mean => Reduced::Scalar(mean(&losses)),
+            Reduction::Sum => Reduced::Scalar(sum(&losses)),
+            Reduction::None => Reduced::PerSample(losses),
+        }
+    }
+}
+
+/// A loss after its [`Reduction`]: one value, or one per sample for
+/// [`Reduction::None`].
+#[derive(Clone, Debug)]
+pub enum Reduced<T = f32> {
+    Scalar(Value<T>),
+    PerSample(Vec<Value<T>>),
+}
+
+impl<T: Scalar> Reduced<T> {
+    /// The reduced loss. Panics for [`Reduced::PerSample`].
+    pub fn into_value(self) -> Value<T> {
+        match self {
+            Reduced::Scalar(value) => value,
+            Reduced::PerSample(_) => panic!("per-sample losses have no single value"),
+        }
+    }
+
+    /// The per-sample losses, or the reduced loss on its own.
+    pub fn into_values(self) -> Vec<Value<T>> {
+        match self {
+            Reduced::Scalar(value) => vec![value],
+            Reduced::PerSample(values) => values,
+        }
+    }
+}
+
+/// Summed squared error between predictions and targets.
+pub fn mse<T: Scalar>(preds: &[Value<T>], targets: &[T]) -> Value<T> {
+    squared_errors(preds, targets).into_iter().sum()
+}
+
+/// Squared error between predictions and targets, combined by `reduction`.
+pub fn mse_with_reduction<T: Scalar>(
+    preds: &[Value<T>],
+    targets: &[T],
+    reduction: Reduction,
+) -> Reduced<T> {
+    reduction.apply(squared_errors(preds, targets))
+}
+
+/// Squared error with each sample's term scaled by its weight before
+/// `reduction`, e.g. to upweight rare classes. [`Reduction::Mean`] divides
+/// by the number of samples, not the total weight.
+pub fn mse_weighted<T: Scalar>(
+    preds: &[Value<T>],
+    targets: &[T],
+    weights: &[T],
+    reduction: Reduction,
+) -> Reduced<T> {
+    reduction.apply(weigh(squared_errors(preds, targets), weights))
+}
+
+fn squared_errors<T: Scalar>(preds: &[Value<T>], targets: &[T]) -> Vec<Value<T>> {
+    assert_eq!(
+        preds.len(),
+        targets.len(),
+        "num of preds ({}) do not equal num of targets ({})",
+        preds.len(),
+        targets.len()
+    );
+
+    preds
+        .iter()
+        .zip(targets)
+        .map(|(pred, target)| (pred - *target).powf(cast(2.0)))
+        .collect()
+}
+
+/// Squared error averaged over the number of samples.
+pub fn mse_mean<T: Scalar>(preds: &[Value<T>], targets: &[T]) -> Value<T> {
+    &mse(preds, targets) / cast::<T>(preds.len() as f64)
+}
+
+/// Summed Huber (smooth L1) loss: `0.5 * r^2` for residuals `|r| <= delta`
+/// and `delta * (|r| - 0.5 * delta)` beyond, so outliers contribute a
+/// gradient of at most `delta`.
+pub fn huber_loss<T: Scalar>(preds: &[Value<T>], targets: &[T], delta: T) -> Value<T> {
+    assert_eq!(
+        preds.len(),
+        targets.len(),
+        "num of preds ({}) do not equal num of targets ({})",
+        preds.len(),
+        targets.len()
+    );
+
+    let delta_value = Value::new(delta);
+
+    preds
+        .iter()
+        .zip(targets)
+        .map(|(pred, target)| {
+            // quadratic up to delta, linear in whatever is left over
+            let residual = (pred - *target).abs();
+            let quadratic = residual.min(&delta_value);
+            let linear = &residual - &quadratic;
+
+            &(&quadratic.powf(cast(2.0)) * cast::<T>(0.5)) + &(&linear * delta)
+        })
+        .sum()
+}
+
+/// Binary cross-entropy `-(y * ln(p) + (1 - y) * ln(1 - p))` averaged over
+/// the samples, for predictions already squashed into (0, 1) by a sigmoid.
+/// Predictions are clamped to `[1e-7, 1 - 1e-7]` first so a saturated output
+/// gives a large but finite loss instead of `ln(0)`.
+pub fn bce_loss<T: Scalar>(preds: &[Value<T>], targets: &[T]) -> Value<T> {
+    bce_with_reduction(preds, targets, Reduction::Mean).into_value()
+}
+
+/// Binary cross-entropy as in [`bce_loss`], combined by `reduction`.
+pub fn bce_with_reduction<T: Scalar>(
+    preds: &[Value<T>],
+    targets: &[T],
+    reduction: Reduction,
+) -> Reduced<T> {
+    reduction.apply(cross_entropies(preds, targets))
+}
+
+/// Binary cross-entropy as in [`bce_loss`] with each sample's term scaled by
+/// its weight before `reduction`, like [`mse_weighted`].
+pub fn bce_weighted<T: Scalar>(
+    preds: &[Value<T>],
+    targets: &[T],
+    weights: &[T],
+    reduction: Reduction,
+) -> Reduced<T> {
+    reduction.apply(weigh(cross_entropies(preds, targets), weights))
+}
+
+fn cross_entropies<T: Scalar>(preds: &[Value<T>], targets: &[T]) -> Vec<Value<T>> {
+    assert_eq!(
+        preds.len(),
+        targets.len(),
+        "num of preds ({}) do not equal num of targets ({})",
+        preds.len(),
+        targets.len()
+    );
+
+    let eps: T = cast(1e-7);
+
+    preds
+        .iter()
+        .zip(targets)
+        .map(|(pred, target)| {
+            let p = pred.clamp(eps, T::one() - eps);
+            let not_p = &(-&p) + T::one();
+
+            -(&(&p.ln() * *target) + &(&not_p.ln() * (T::one() - *target)))
+        })
+        .collect()
+}
+
+// weights enter as plain scalars, so they stay constants of the graph
+fn weigh<T: Scalar>(losses: Vec<Value<T>>, weights: &[T]) -> Vec<Value<T>> {
+    assert_eq!(
+        losses.len(),
+        weights.len(),
+        "num of samples ({}) do not equal num of weights ({})",
+        losses.len(),
+        weights.len()
+    );
+
+    losses
+        .iter()
+        .zip(weights)
+        .map(|(loss, weight)| loss * *weight)
+        .collect()
+}
+
+/// Hinge loss `max(0, 1 - y * pred)` averaged over the samples, for targets
+/// of `1` or `-1`. Predictions beyond the margin get no gradient.
+pub fn hinge_loss<T: Scalar>(preds: &[Value<T>], targets: &[T]) -> Value<T> {
+    assert_eq!(
+        preds.len(),
+        targets.len(),
+        "num of preds ({}) do not equal num of targets ({})",
+        preds.len(),
+        targets.len()
+    );
+
+    let zero = Value::new(T::zero());
+    let losses: Vec<Value<T>> = preds
+        .iter()
+        .zip(targets)
+        // zero on the left so a prediction exactly on the margin, where
+        // max picks its left side, also gets no gradient
+        .map(|(pred, target)| zero.max(&(&(-&(pred * *target)) + T::one())))
+        .collect();
+
+    mean(&losses)
+}
+
+/// Negative log-likelihood of `target_index` under a softmax of `logits`.
+///
+/// Computed as `logsumexp(logits) - logits[target_index]` with the max logit
+/// factored out as a constant, so no explicit softmax is ever materialized.
+pub fn cross_entropy<T: Scalar>(logits: &[Value<T>], target_index: usize) -> Value<T> {
+    assert!(
+        target_index < logits.len(),
+        "target index ({}) out of range for {} logits",
+        target_index,
+        logits.len()
+    );
+
+    let max = logits
+        .iter()
+        .map(|l| l.data())
+        .fold(T::neg_infinity(), T::max);
+
+    let sum_exp: Value<T> = logits.iter().map(|l| (l - max).exp()).sum();
+    let logsumexp = &sum_exp.ln() + max;
+    logsumexp.set_op("logsumexp");
+
+    let loss = &logsumexp - &logits[target_index];
+    loss.set_op("cross_entropy");
+
+    loss
+}
+
+/// `lambda * sum(p^2)` over the given parameters, to be added to a loss.
+pub fn l2_penalty<T: Scalar>(params: &[Value<T>], lambda: T) -> Value<T> {
+    &params.iter().map(|p| p.powf(cast(2.0))).sum::<Value<T>>() * lambda
+}
+
+/// `lambda * sum(|p|)` over the given parameters, to be added to a loss.
+pub fn l1_penalty<T: Scalar>(params: &[Value<T>], lambda: T) -> Value<T> {
+    &params.iter().map(|p| p.abs()).sum::<Value<T>>() * lambda
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{functional::softmax, value::Value};
+
+    use super::{
+        bce_loss, bce_weighted, bce_with_reduction, cross_entropy, hinge_loss, huber_loss,
+        l1_penalty, l2_penalty, mse, mse_mean, mse_weighted, mse_with_reduction, Reduced,
+        Reduction,
+    };
+
+    #[test]
+    fn test_mse() {
+        let w = &Value::new(2.0_f32);
+        let xs = [1.0, -1.0, 3.0];
+        let targets = [1.0, 0.0, 5.0];
+
+        let preds: Vec<Value> = xs.iter().map(|x| w * *x).collect();
+        let loss = mse(&preds, &targets);
+        loss.backward();
+
+        // preds are 2, -2, 6 so the residuals are 1, -2, 1
+        assert_eq!(loss.data(), 1.0 + 4.0 + 1.0);
+        // d/dw sum((w*x - y)^2) = sum(2 * (w*x - y) * x) = 2 + 4 + 6
+        assert_eq!(w.grad(), 12.0);
+    }
+
+    #[test]
+    fn test_mse_mean() {
+        let preds = [Value::new(2.0_f32), Value::new(-2.0), Value::new(6.0)];
+        let targets = [1.0, 0.0, 5.0];
+
+        let loss = mse_mean(&preds, &targets);
+        loss.backward();
+
+        assert_eq!(loss.data(), 2.0);
+        assert_eq!(preds[1].grad(), 2.0 * -2.0 / 3.0);
+    }
+
+    #[test]
+    fn test_mse_with_reduction() {
+        let run = |reduction| {
+            let preds = [Value::new(2.0_f64), Value::new(-2.0), Value::new(6.0)];
+            let loss = mse_with_reduction(&preds, &[1.0, 0.0, 5.0], reduction);
+            (preds, loss)
+        };
+
+        let (sum_preds, sum) = run(Reduction::Sum);
+        let sum = sum.into_value();
+        sum.backward();
+        let (mean_preds, mean) = run(Reduction::Mean);
+        let mean = mean.into_value();
+        mean.backward();
+
+        assert_eq!(sum.data(), 6.0);
+        assert_eq!(mean.data(), sum.data() / 3.0);
+        for (s, m) in sum_preds.iter().zip(&mean_preds) {
+            assert!((m.grad() - s.grad() / 3.0).abs() < 1e-12);
+        }
+
+        let (_, per_sample) = run(Reduction::None);
+        assert!(matches!(per_sample, Reduced::PerSample(_)));
+        let data: Vec<f64> = per_sample.into_values().iter().map(|l| l.data()).collect();
+        assert_eq!(data, vec![1.0, 4.0, 1.0]);
+
+        assert_eq!(Reduction::default(), Reduction::Mean);
+    }
+
+    #[test]
+    fn test_bce_with_reduction() {
+        let preds = [Value::new(0.8_f64), Value::new(0.4)];
+        let targets = [1.0, 0.0];
+
+        let per_sample = bce_with_reduction(&preds, &targets, Reduction::None).into_values();
+        let sum = bce_with_reduction(&preds, &targets, Reduction::Sum).into_value();
+
+        assert!((per_sample[0].data() - -(0.8_f64.ln())).abs() < 1e-12);
+        assert!((per_sample[1].data() - -(0.6_f64.ln())).abs() < 1e-12);
+        assert!((sum.data() - 2.0 * bce_loss(&preds, &targets).data()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_weighted_losses() {
+        // one parameter per sample, so each grad is that sample's share
+        let grads = |bce: bool, weights: &[f64]| {
+            let params = [Value::new(0.3), Value::new(0.6)];
+            let preds: Vec<Value<f64>> = params.iter().map(|p| p * 1.0).collect();
+            let targets = [1.0, 0.0];
+
+            let loss = if bce {
+                bce_weighted(&preds, &targets, weights, Reduction::Mean)
+            } else {
+                mse_weighted(&preds, &targets, weights, Reduction::Sum)
+            };
+            loss.into_value().backward();
+
+            params.iter().map(|p| p.grad()).collect::<Vec<f64>>()
+        };
+
+        for bce in [false, true] {
+            let plain = grads(bce, &[1.0, 1.0]);
+            let doubled = grads(bce, &[2.0, 1.0]);
+
+            assert!((doubled[0] - 2.0 * plain[0]).abs() < 1e-12);
+            assert_eq!(doubled[1], plain[1]);
+        }
+
+        // unit weights change nothing
+        let preds = [Value::new(0.3), Value::new(0.6)];
+        let weighted = mse_weighted(&preds, &[1.0, 0.0], &[1.0, 1.0], Reduction::Mean);
+        let plain = mse_with_reduction(&preds, &[1.0, 0.0], Reduction::Mean);
+        assert_eq!(weighted.into_value().data(), plain.into_value().data());
+    }
+
+    #[test]
+    #[should_panic(expected = "num of samples (2) do not equal num of weights (1)")]
+    fn test_weighted_loss_length_mismatch() {
+        let preds = [Value::new(0.3), Value::new(0.6)];
+        mse_weighted(&preds, &[1.0, 0.0], &[1.0], Reduction::Sum);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mse_length_mismatch() {
+        mse(&[Value::new(1.0_f32)], &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_huber_loss_small_residuals() {
+        let preds = [Value::new(1.5_f64), Value::new(-0.25)];
+        let targets = [1.0, 0.0];
+
+        let huber = huber_loss(&preds, &targets, 1.0);
+        huber.backward();
+        let huber_grads: Vec<f64> = preds.iter().map(|p| p.grad()).collect();
+
+        preds.iter().for_each(|p| p.zero_grad());
+        let squared = mse(&preds, &targets);
+        squared.backward();
+
+        // inside delta huber is exactly half the squared error
+        assert_eq!(huber.data(), 0.5 * squared.data());
+        for (p, huber_grad) in preds.iter().zip(huber_grads) {
+            assert_eq!(huber_grad, 0.5 * p.grad());
+        }
+    }
+
+    #[test]
+    fn test_huber_loss_large_residuals() {
+        let preds = [Value::new(101.0_f64), Value::new(-50.0)];
+        let targets = [1.0, 0.0];
+
+        let loss = huber_loss(&preds, &targets, 2.0);
+        loss.backward();
+
+        // 0.5 * 2^2 + 2 * (100 - 2) and 0.5 * 2^2 + 2 * (50 - 2)
+        assert_eq!(loss.data(), 198.0 + 98.0);
+        // the gradient is capped at delta however far off the prediction is
+        assert_eq!(preds[0].grad(), 2.0);
+        assert_eq!(preds[1].grad(), -2.0);
+    }
+
+    #[test]
+    fn test_bce_loss() {
+        let preds = [Value::new(0.8_f64), Value::new(0.4)];
+        let targets = [1.0, 0.0];
+
+        let loss = bce_loss(&preds, &targets);
+        loss.backward();
+
+        // -(ln(0.8) + ln(1 - 0.4)) / 2
+        let expected = -(0.8_f64.ln() + 0.6_f64.ln()) / 2.0;
+        assert!((loss.data() - expected).abs() < 1e-12);
+
+        // d/dp is -1 / p for y = 1 and 1 / (1 - p) for y = 0, halved by the mean
+        assert!((preds[0].grad() - -1.0 / (0.8 * 2.0)).abs() < 1e-12);
+        assert!((preds[1].grad() - 1.0 / (0.6 * 2.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_bce_loss_saturated() {
+        let preds = [Value::new(0.0_f32), Value::new(1.0)];
+
+        let loss = bce_loss(&preds, &[1.0, 0.0]);
+
+        assert!(loss.data().is_finite());
+        assert!(loss.data() > 10.0);
+    }
+
+    #[test]
+    fn test_hinge_loss_margin_satisfied() {
+        let preds = [Value::new(1.5_f64), Value::new(-2.0), Value::new(1.0)];
+
+        let loss = hinge_loss(&preds, &[1.0, -1.0, 1.0]);
+        loss.backward();
+
+        assert_eq!(loss.data(), 0.0);
+        assert!(preds.iter().all(|p| p.grad() == 0.0));
+    }
+
+    #[test]
+    fn test_hinge_loss_margin_violated() {
+        let preds = [Value::new(0.5_f64), Value::new(0.25), Value::new(3.0)];
+
+        let loss = hinge_loss(&preds, &[1.0, -1.0, 1.0]);
+        loss.backward();
+
+        // (0.5 + 1.25 + 0) / 3
+        assert_eq!(loss.data(), 1.75 / 3.0);
+        let grads: Vec<f64> = preds.iter().map(|p| p.grad()).collect();
+        assert_eq!(grads, vec![-1.0 / 3.0, 1.0 / 3.0, 0.0]);
+    }
+
+    #[test]
+    fn test_cross_entropy() {
+        let logits = [Value::new(1.0_f32), Value::new(2.0)];
+
+        let loss = cross_entropy(&logits, 0);
+        loss.backward();
+
+        // -ln(e^1 / (e^1 + e^2)) = ln(1 + e)
+        let expected = (1.0 + std::f32::consts::E).ln();
+        assert!(
+            (loss.data() - expected).abs() < 1e-6,
+            "loss {}",
+            loss.data()
+        );
+        assert_eq!(loss.op(), String::from("cross_entropy"));
+
+        // gradient is softmax - onehot
+        let probs = softmax(&logits);
+        let onehot = [1.0, 0.0];
+        for ((logit, prob), target) in logits.iter().zip(probs).zip(onehot) {
+            assert!((logit.grad() - (prob.data() - target)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_l2_penalty() {
+        let params = [Value::new(3.0_f32), Value::new(-3.0)];
+        let targets = [2.0, -2.0];
+
+        let loss = mse(&params, &targets);
+        loss.backward();
+        let plain: Vec<f32> = params.iter().map(|p| p.grad()).collect();
+
+        params.iter().for_each(|p| p.zero_grad());
+        let loss = &mse(&params, &targets) + &l2_penalty(&params, 0.5);
+        loss.backward();
+
+        // d/dp 0.5 * p^2 = p, so the penalty pulls each weight towards zero
+        for (p, plain) in params.iter().zip(plain) {
+            assert_eq!(p.grad(), plain + p.data());
+            assert!(p.grad().abs() > plain.abs());
+            assert_eq!(p.grad().signum(), p.data().signum());
+        }
+    }
+
+    #[test]
+    fn test_l1_penalty() {
+        let params = [Value::new(3.0_f32), Value::new(-3.0)];
+
+        let penalty = l1_penalty(&params, 0.5);
+        penalty.backward();
+
+        assert_eq!(penalty.data(), 3.0);
+        assert_eq!(params[0].grad(), 0.5);
+        assert_eq!(params[1].grad(), -0.5);
+    }
+}
@@ -0,0 +1,121 @@
+//! src/metrics.rs
+
+use crate::{
+    mlp::Mlp,
+    value::{Scalar, Value},
+};
+
+/// Index of the largest value. Ties go to the lowest index.
+pub fn argmax<T: Scalar>(values: &[Value<T>]) -> usize {
+    assert!(!values.is_empty(), "argmax of an empty slice");
+
+    values
+        .iter()
+        .enumerate()
+        .skip(1)
+        .fold(0, |best, (i, v)| if *v > values[best] { i } else { best })
+}
+
+/// Fraction of predictions whose argmax matches the target class.
+pub fn accuracy<T: Scalar>(preds: &[Vec<Value<T>>], targets: &[usize]) -> f32 {
+    assert_eq!(
+        preds.len(),
+        targets.len(),
+        "num of preds ({}) do not equal num of targets ({})",
+        preds.len(),
+        targets.len()
+    );
+
+    if preds.is_empty() {
+        return 0.0;
+    }
+
+    let correct = preds
+        .iter()
+        .zip(targets)
+        .filter(|(pred, target)| argmax(pred) == **target)
+        .count();
+
+    correct as f32 / preds.len() as f32
+}
+
+/// Cosine of the angle between two same-shaped networks' flattened
+/// parameters, e.g. to see how far fine-tuning moved a model from its
+/// initialization. 0 if either network's parameters are all zero.
+pub fn param_cosine_similarity<T: Scalar>(a: &Mlp<T>, b: &Mlp<T>) -> f32 {
+    assert_eq!(
+        a.architecture(),
+        b.architecture(),
+        "architecture of a ({:?}) does not equal architecture of b ({:?})",
+        a.architecture(),
+        b.architecture()
+    );
+
+    let (dot, norm_a, norm_b) = a.parameters_iter().zip(b.parameters_iter()).fold(
+        (0.0, 0.0, 0.0),
+        |(dot, norm_a, norm_b), (pa, pb)| {
+            let (x, y) = (pa.data().to_f64().unwrap(), pb.data().to_f64().unwrap());
+            (dot + x * y, norm_a + x * x, norm_b + y * y)
+        },
+    );
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
+    (dot / (norm_a.sqrt() * norm_b.sqrt())) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mlp::Mlp, value::Value};
+
+    use super::{accuracy, argmax, param_cosine_similarity};
+
+    #[test]
+    fn test_argmax() {
+        assert_eq!(argmax(&Value::from_slice(&[0.1_f32, 0.7, 0.2])), 1);
+        assert_eq!(argmax(&Value::from_slice(&[-3.0_f32])), 0);
+
+        // ties go to the lowest index
+        assert_eq!(argmax(&Value::from_slice(&[0.5_f32, 0.9, 0.9, 0.1])), 1);
+        assert_eq!(argmax(&Value::from_slice(&[1.0_f32, 1.0])), 0);
+    }
+
+    #[test]
+    fn test_accuracy() {
+        let preds = vec![
+            Value::from_slice(&[0.9_f32, 0.1]),
+            Value::from_slice(&[0.2, 0.8]),
+            Value::from_slice(&[0.6, 0.4]),
+            Value::from_slice(&[0.3, 0.7]),
+        ];
+
+        assert_eq!(accuracy(&preds, &[0, 1, 1, 1]), 0.75);
+        assert_eq!(accuracy(&preds, &[0, 1, 0, 1]), 1.0);
+        assert_eq!(accuracy::<f32>(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn test_param_cosine_similarity() {
+        let a = Mlp::<f32>::new_seeded(3, vec![4, 1], 42);
+        let b = Mlp::<f32>::new_seeded(3, vec![4, 1], 42);
+        assert!((param_cosine_similarity(&a, &b) - 1.0).abs() < 1e-6);
+        assert!((param_cosine_similarity(&a, &a) - 1.0).abs() < 1e-6);
+
+        let negated: Vec<f32> = a.get_parameters_flat().iter().map(|p| -p).collect();
+        b.set_parameters_from(&negated);
+        assert!((param_cosine_similarity(&a, &b) + 1.0).abs() < 1e-6);
+
+        b.set_parameters_from(&vec![0.0; b.parameter_count()]);
+        assert_eq!(param_cosine_similarity(&a, &b), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "architecture of a")]
+    fn test_param_cosine_similarity_shape_mismatch() {
+        let a = Mlp::<f32>::new(3, vec![4, 1]);
+        let b = Mlp::<f32>::new(3, vec![5, 1]);
+        param_cosine_similarity(&a, &b);
+    }
+}
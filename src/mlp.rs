@@ -1,17 +1,104 @@
 //! src/mlp.rs
 
-use crate::{layer::Layer, value::Value};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::Path,
+};
 
-#[derive(Clone, Debug)]
-pub struct Mlp(Vec<Layer>);
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-impl Mlp {
-    pub fn new(mut nin: usize, nouts: Vec<usize>) -> Mlp {
-        let layers = nouts
+use crate::{
+    activation::Activation,
+    error::Error,
+    init::Init,
+    layer::Layer,
+    module::Module,
+    value::{cast, Scalar, Value},
+};
+
+/// A JSON dump of a network from Karpathy's Python micrograd: the
+/// `MLP(nin, nouts)` arguments and `[p.data for p in model.parameters()]`.
+#[derive(Deserialize)]
+struct PythonState {
+    nin: usize,
+    nouts: Vec<usize>,
+    parameters: Vec<f64>,
+}
+
+/// Summary of every parameter gradient in a network, see
+/// [`Mlp::grad_stats`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GradStats<T = f32> {
+    pub min: T,
+    pub max: T,
+    pub mean: T,
+    /// L2 norm of all the gradients taken as one vector.
+    pub norm: T,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Scalar + Serialize",
+    deserialize = "T: Scalar + Deserialize<'de>"
+))]
+pub struct Mlp<T = f32>(Vec<Layer<T>>);
+
+impl<T: Scalar> Mlp<T> {
+    pub fn new(nin: usize, nouts: Vec<usize>) -> Mlp<T> {
+        Mlp::new_with_init(nin, nouts, Init::Uniform)
+    }
+
+    /// Like [`Mlp::new`], but the last layer uses `output` instead of tanh,
+    /// e.g. [`Activation::Linear`] for regression or for logits.
+    pub fn new_with_output(nin: usize, nouts: Vec<usize>, output: Activation) -> Mlp<T> {
+        let mlp = Mlp::new(nin, nouts);
+        if let Some(last) = mlp.layers().last() {
+            last.set_activation(output);
+        }
+
+        mlp
+    }
+
+    pub fn new_with_init(nin: usize, nouts: Vec<usize>, init: Init) -> Mlp<T> {
+        Mlp::new_with_rng(nin, nouts, init, &mut rand::thread_rng())
+    }
+
+    /// Builds a network whose initial weights depend only on `seed`.
+    pub fn new_seeded(nin: usize, nouts: Vec<usize>, seed: u64) -> Mlp<T> {
+        Mlp::new_with_rng(nin, nouts, Init::Uniform, &mut StdRng::seed_from_u64(seed))
+    }
+
+    pub fn new_with_rng<R: Rng + ?Sized>(
+        nin: usize,
+        nouts: Vec<usize>,
+        init: Init,
+        rng: &mut R,
+    ) -> Mlp<T> {
+        let layers: Vec<(usize, Activation)> =
+            nouts.iter().map(|l| (*l, Activation::Tanh)).collect();
+
+        Mlp::new_with_activations_and_rng(nin, &layers, init, rng)
+    }
+
+    /// Builds a network from `(nout, activation)` per layer, e.g.
+    /// `[(16, Relu), (16, Relu), (1, Linear)]`.
+    pub fn new_with_activations(nin: usize, layers: &[(usize, Activation)]) -> Mlp<T> {
+        Mlp::new_with_activations_and_rng(nin, layers, Init::Uniform, &mut rand::thread_rng())
+    }
+
+    pub fn new_with_activations_and_rng<R: Rng + ?Sized>(
+        mut nin: usize,
+        layers: &[(usize, Activation)],
+        init: Init,
+        rng: &mut R,
+    ) -> Mlp<T> {
+        let layers = layers
             .iter()
-            .map(|l| {
-                let layer = Layer::new(nin, *l);
-                nin = *l;
+            .map(|(nout, activation)| {
+                let layer = Layer::new_with_rng(nin, *nout, *activation, init, rng);
+                nin = *nout;
                 layer
             })
             .collect();
@@ -19,8 +106,36 @@ impl Mlp {
         Mlp(layers)
     }
 
-    pub fn call(&self, inputs: &Vec<Value>) -> Vec<Value> {
-        let mut output: Vec<Value> = inputs.clone();
+    /// Builds the network described by a Python micrograd dump, a JSON
+    /// object with `nin`, `nouts` and a flat `parameters` list. As in Python,
+    /// hidden layers use ReLU and the last layer is linear; both order
+    /// parameters as each neuron's weights then its bias, layer by layer.
+    pub fn from_python_state(json: &str) -> Result<Mlp<T>, Error> {
+        let state: PythonState = serde_json::from_str(json)?;
+
+        let mlp = Mlp::new_with_output(state.nin, state.nouts, Activation::Linear);
+        if let Some((_, hidden)) = mlp.layers().split_last() {
+            hidden
+                .iter()
+                .for_each(|l| l.set_activation(Activation::Relu));
+        }
+
+        if state.parameters.len() != mlp.parameter_count() {
+            return Err(Error::ParameterCount {
+                expected: mlp.parameter_count(),
+                found: state.parameters.len(),
+            });
+        }
+
+        let flat: Vec<T> = state.parameters.into_iter().map(cast).collect();
+        mlp.set_parameters_from(&flat);
+
+        Ok(mlp)
+    }
+
+    #[allow(clippy::ptr_arg)]
+    pub fn call(&self, inputs: &Vec<Value<T>>) -> Vec<Value<T>> {
+        let mut output: Vec<Value<T>> = inputs.clone();
 
         self.layers().iter().for_each(|l| {
             output = l.call(&output);
@@ -29,27 +144,277 @@ impl Mlp {
         output
     }
 
-    pub fn callf(&self, inputs: &Vec<f32>) -> Vec<Value> {
-        let inputs = inputs.iter().map(|v| Value::new(v.clone())).collect();
-        
+    #[allow(clippy::ptr_arg)]
+    pub fn callf(&self, inputs: &Vec<T>) -> Vec<Value<T>> {
+        let inputs = Value::from_slice(inputs);
+
         self.call(&inputs)
     }
 
-    pub fn parameters(&self) -> Vec<Value> {
+    /// Runs every sample through the same parameter nodes, so a loss summed
+    /// over the batch accumulates gradients from all samples.
+    pub fn call_batch(&self, inputs: &[Vec<T>]) -> Vec<Vec<Value<T>>> {
+        inputs.iter().map(|x| self.callf(x)).collect()
+    }
+
+    /// Runs the batch forward and applies `loss_fn` to the first output of
+    /// each prediction, returning the loss ready for `backward`.
+    pub fn forward_loss<F>(&self, xs: &[Vec<T>], ys: &[T], loss_fn: F) -> Value<T>
+    where
+        F: Fn(&[Value<T>], &[T]) -> Value<T>,
+    {
+        let preds: Vec<Value<T>> = xs.iter().map(|x| self.callf(x)[0].clone()).collect();
+
+        loss_fn(&preds, ys)
+    }
+
+    pub fn parameters(&self) -> Vec<Value<T>> {
+        self.parameters_iter().collect()
+    }
+
+    /// Lazy version of [`Mlp::parameters`] for loops that only need to visit
+    /// each parameter once.
+    pub fn parameters_iter(&self) -> impl Iterator<Item = Value<T>> + '_ {
+        self.layers().iter().flat_map(|l| l.parameters_iter())
+    }
+
+    /// Data of every parameter, in [`Mlp::parameters`] order.
+    pub fn get_parameters_flat(&self) -> Vec<T> {
+        self.parameters_iter().map(|p| p.data()).collect()
+    }
+
+    /// Writes `flat` into the existing parameters, in [`Mlp::parameters`]
+    /// order. Anything holding the parameters sees the new values.
+    pub fn set_parameters_from(&self, flat: &[T]) {
+        let params = self.parameters();
+        assert_eq!(
+            flat.len(),
+            params.len(),
+            "num of values ({}) do not equal num of parameters ({})",
+            flat.len(),
+            params.len()
+        );
+
+        params.iter().zip(flat).for_each(|(p, x)| p.set_data(*x));
+    }
+
+    /// Resamples every weight and bias from [`Init::Uniform`], keeping the
+    /// architecture and activations. The new numbers are written into the
+    /// existing parameters, so optimizers built from [`Mlp::parameters`] keep
+    /// training the network. With `seed` the new weights are reproducible.
+    pub fn reinit(&self, seed: Option<u64>) {
+        self.reinit_with(Init::Uniform, seed);
+    }
+
+    /// [`Mlp::reinit`] sampling from `init` instead.
+    pub fn reinit_with(&self, init: Init, seed: Option<u64>) {
+        let mut rng = seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+
         self.layers()
             .iter()
-            .map(|l| l.parameters())
-            .collect::<Vec<Vec<Value>>>()
-            .concat()
+            .flat_map(|l| l.neurons())
+            .for_each(|n| {
+                let (weights, bias) = init.sample(n.num_weights(), &mut rng);
+
+                n.weights()
+                    .iter()
+                    .zip(weights)
+                    .for_each(|(w, x)| w.set_data(cast(x)));
+                n.bias().set_data(cast(bias));
+            });
+    }
+
+    /// Sets every weight with `|data| < threshold` to exactly zero, in place.
+    /// Biases are kept.
+    pub fn prune(&self, threshold: T) {
+        self.weights_iter()
+            .filter(|w| w.data().abs() < threshold)
+            .for_each(|w| w.set_data(T::zero()));
+    }
+
+    /// Fraction of weights that are exactly zero, 0 without any weights.
+    pub fn sparsity(&self) -> f32 {
+        let (zeros, total) = self.weights_iter().fold((0, 0), |(zeros, total), w| {
+            (zeros + usize::from(w.data() == T::zero()), total + 1)
+        });
+
+        if total == 0 {
+            return 0.0;
+        }
+
+        zeros as f32 / total as f32
     }
 
-    pub fn layers(&self) -> &Vec<Layer> {
+    /// Counts of weight values, biases excluded, in `bins` equal-width buckets
+    /// spanning `[min, max]`, each paired with its bucket's lower edge. The
+    /// maximum lands in the last bucket; if every weight is the same they all
+    /// land in the first. Empty without any weights.
+    pub fn weight_histogram(&self, bins: usize) -> Vec<(T, usize)> {
+        assert!(bins > 0, "bins must be at least 1");
+
+        let data: Vec<T> = self.weights_iter().map(|w| w.data()).collect();
+        if data.is_empty() {
+            return vec![];
+        }
+
+        let min = data.iter().fold(T::infinity(), |acc, x| acc.min(*x));
+        let max = data.iter().fold(T::neg_infinity(), |acc, x| acc.max(*x));
+        let width = (max - min) / cast(bins as f64);
+
+        let mut counts = vec![0; bins];
+        for x in data {
+            let bin = if width > T::zero() {
+                ((x - min) / width).to_usize().unwrap_or(0).min(bins - 1)
+            } else {
+                0
+            };
+            counts[bin] += 1;
+        }
+
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| (min + width * cast(i as f64), count))
+            .collect()
+    }
+
+    fn weights_iter(&self) -> impl Iterator<Item = Value<T>> + '_ {
+        self.layers()
+            .iter()
+            .flat_map(|l| l.neurons())
+            .flat_map(|n| n.weights())
+    }
+
+    pub fn parameter_count(&self) -> usize {
+        self.layers().iter().map(|l| l.parameter_count()).sum()
+    }
+
+    pub fn zero_grad(&self) {
+        self.parameters_iter().for_each(|p| p.zero_grad());
+    }
+
+    pub fn layers(&self) -> &Vec<Layer<T>> {
         &self.0
     }
+
+    /// Min, max, mean and L2 norm of the parameter gradients, all zero for a
+    /// network without parameters. Use [`Layer::grad_norm`] to see which
+    /// layer is responsible.
+    pub fn grad_stats(&self) -> GradStats<T> {
+        let grads: Vec<T> = self.parameters_iter().map(|p| p.grad()).collect();
+        if grads.is_empty() {
+            return GradStats {
+                min: T::zero(),
+                max: T::zero(),
+                mean: T::zero(),
+                norm: T::zero(),
+            };
+        }
+
+        let sum = grads.iter().fold(T::zero(), |acc, g| acc + *g);
+
+        GradStats {
+            min: grads.iter().fold(T::infinity(), |acc, g| acc.min(*g)),
+            max: grads.iter().fold(T::neg_infinity(), |acc, g| acc.max(*g)),
+            mean: sum / cast(grads.len() as f64),
+            norm: grads.iter().fold(T::zero(), |acc, g| acc + *g * *g).sqrt(),
+        }
+    }
+
+    /// The `(nin, nouts)` the network was built from, so
+    /// `Mlp::new(nin, nouts)` gives a fresh network of the same shape. `nin`
+    /// is 0 for a network without layers.
+    pub fn architecture(&self) -> (usize, Vec<usize>) {
+        let nin = self.layers().first().map_or(0, |l| l.nin());
+        let nouts = self.layers().iter().map(|l| l.nout()).collect();
+
+        (nin, nouts)
+    }
+
+    /// Table of each layer's shape, activation and parameter count, followed
+    /// by the total parameter count.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{:<8}{:<8}{:<8}{:<12}{}\n",
+            "Layer", "Input", "Output", "Activation", "Params"
+        );
+
+        self.layers().iter().enumerate().for_each(|(i, l)| {
+            let activation = l
+                .neurons()
+                .first()
+                .map_or(String::from("-"), |n| format!("{:?}", n.activation()));
+
+            summary += &format!(
+                "{:<8}{:<8}{:<8}{:<12}{}\n",
+                i,
+                l.nin(),
+                l.nout(),
+                activation,
+                l.parameter_count()
+            );
+        });
+
+        summary + &format!("Total params: {}", self.parameter_count())
+    }
+}
+
+impl<T: Scalar> Module<T> for Mlp<T> {
+    fn call(&self, inputs: &[Value<T>]) -> Vec<Value<T>> {
+        Mlp::call(self, &inputs.to_vec())
+    }
+
+    fn parameters(&self) -> Vec<Value<T>> {
+        Mlp::parameters(self)
+    }
+}
+
+impl<T: Scalar + Serialize> Mlp<T> {
+    /// Writes the weights, biases and activations as JSON. Gradients and
+    /// any computation graph hanging off the parameters are not saved.
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+
+        Ok(serde_json::to_writer(writer, self)?)
+    }
+
+    /// Same contents as [`Mlp::save_json`] in bincode's compact binary
+    /// format: the layer and neuron counts, then the raw floats.
+    pub fn save_bincode<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+
+        bincode::serialize_into(writer, self).map_err(io::Error::other)
+    }
+}
+
+impl<T: Scalar + DeserializeOwned> Mlp<T> {
+    /// Rebuilds a network saved with [`Mlp::save_json`] from fresh leaf values.
+    pub fn load_json<P: AsRef<Path>>(path: P) -> io::Result<Mlp<T>> {
+        let reader = BufReader::new(File::open(path)?);
+
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Rebuilds a network saved with [`Mlp::save_bincode`] from fresh leaf
+    /// values.
+    pub fn load_bincode<P: AsRef<Path>>(path: P) -> io::Result<Mlp<T>> {
+        let reader = BufReader::new(File::open(path)?);
+
+        bincode::deserialize_from(reader).map_err(io::Error::other)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::{
+        activation::Activation,
+        error::Error,
+        init::Init,
+        layer::Layer,
+        optim::{Optimizer, Sgd},
+        value::Value,
+    };
+
     use super::Mlp;
 
     #[test]
@@ -66,4 +431,413 @@ mod tests {
         println!("mlp out: {:?}", out);
         println!("mlp params: {:?}", mlp.parameters());
     }
+
+    #[test]
+    fn test_mlp_parameter_count() {
+        let mlp = Mlp::<f32>::new(3, vec![4, 4, 1]);
+
+        assert_eq!(mlp.parameter_count(), (3 * 4 + 4) + (4 * 4 + 4) + (4 + 1));
+        assert_eq!(mlp.parameter_count(), mlp.parameters().len());
+        assert_eq!(mlp.layers()[0].parameter_count(), 3 * 4 + 4);
+        assert_eq!(mlp.layers()[0].neurons()[0].parameter_count(), 3 + 1);
+    }
+
+    #[test]
+    fn test_mlp_architecture() {
+        let mlp = Mlp::<f32>::new(3, vec![4, 4, 1]);
+        assert_eq!(mlp.architecture(), (3, vec![4, 4, 1]));
+
+        let (nin, nouts) = mlp.architecture();
+        let fresh = Mlp::<f32>::new(nin, nouts);
+
+        assert_eq!(fresh.architecture(), mlp.architecture());
+        assert_eq!(fresh.summary(), mlp.summary());
+        assert_ne!(fresh.get_parameters_flat(), mlp.get_parameters_flat());
+
+        assert_eq!(Mlp::<f32>::new(3, vec![]).architecture(), (0, vec![]));
+    }
+
+    #[test]
+    fn test_mlp_summary() {
+        let mlp = Mlp::<f32>::new(3, vec![4, 4, 1]);
+
+        let summary = mlp.summary();
+        let lines: Vec<&str> = summary.lines().collect();
+
+        // header, one row per layer, total
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].starts_with("0       3       4       Tanh        16"));
+        assert!(lines[3].starts_with("2       4       1       Tanh        5"));
+        assert_eq!(lines[4], "Total params: 41");
+    }
+
+    #[test]
+    fn test_mlp_call_batch() {
+        let mlp = Mlp::<f64>::new_seeded(3, vec![4, 2], 7);
+        let xs = vec![
+            vec![2.0, 3.0, -1.0],
+            vec![3.0, -1.0, 0.5],
+            vec![0.5, 1.0, 1.0],
+        ];
+
+        let mut per_sample = vec![0.0; mlp.parameter_count()];
+        for x in xs.iter() {
+            let out: Value<f64> = mlp.callf(x).into_iter().sum();
+
+            mlp.zero_grad();
+            out.backward();
+            per_sample
+                .iter_mut()
+                .zip(mlp.parameters())
+                .for_each(|(g, p)| *g += p.grad());
+        }
+
+        let outs = mlp.call_batch(&xs);
+        let batch: Value<f64> = outs.into_iter().flatten().sum();
+
+        mlp.zero_grad();
+        batch.backward();
+
+        assert_eq!(mlp.call_batch(&xs).len(), xs.len());
+        mlp.parameters()
+            .iter()
+            .zip(per_sample)
+            .for_each(|(p, g)| assert!((p.grad() - g).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_mlp_linear_output() {
+        let mlp = Mlp::<f32>::new_with_output(3, vec![4, 2], Activation::Linear);
+
+        let activations = |l: &Layer<f32>| -> Vec<Activation> {
+            l.neurons().iter().map(|n| n.activation()).collect()
+        };
+        assert_eq!(activations(&mlp.layers()[0]), vec![Activation::Tanh; 4]);
+        assert_eq!(activations(&mlp.layers()[1]), vec![Activation::Linear; 2]);
+
+        // blow up the last layer's weights, a tanh output would stay in [-1, 1]
+        mlp.layers()[1]
+            .parameters()
+            .iter()
+            .for_each(|p| p.set_data(p.data() * 100.0));
+        let out = mlp.callf(&vec![2.0, 3.0, -1.0]);
+        assert!(
+            out.iter().any(|o| o.data().abs() > 1.0),
+            "outputs {:?}",
+            out
+        );
+
+        let layer = Layer::<f32>::new_linear(3, 2);
+        assert_eq!(activations(&layer), vec![Activation::Linear; 2]);
+    }
+
+    #[test]
+    fn test_mlp_parameters_iter() {
+        let mlp = Mlp::<f32>::new(3, vec![4, 4, 1]);
+
+        let ids = |params: Vec<Value<f32>>| params.iter().map(|p| p.id()).collect::<Vec<_>>();
+        let expected = ids(mlp.parameters());
+
+        assert_eq!(ids(mlp.parameters_iter().collect()), expected);
+        assert_eq!(mlp.parameters_iter().count(), mlp.parameter_count());
+
+        let layer = &mlp.layers()[1];
+        assert_eq!(
+            ids(layer.parameters_iter().collect()),
+            expected[16..36].to_vec()
+        );
+
+        let neuron = &layer.neurons()[0];
+        assert_eq!(
+            ids(neuron.parameters_iter().collect()),
+            ids(neuron.parameters())
+        );
+    }
+
+    #[test]
+    fn test_mlp_flat_parameters() {
+        let source = Mlp::<f32>::new_seeded(3, vec![4, 1], 1);
+        let target = Mlp::<f32>::new_seeded(3, vec![4, 1], 2);
+        let held = target.parameters();
+
+        let flat = source.get_parameters_flat();
+        assert_eq!(flat.len(), source.parameter_count());
+
+        target.set_parameters_from(&flat);
+
+        assert_eq!(target.get_parameters_flat(), flat);
+        assert_eq!(held.iter().map(|p| p.data()).collect::<Vec<_>>(), flat);
+        assert_eq!(
+            target.callf(&vec![2.0, 3.0, -1.0]),
+            source.callf(&vec![2.0, 3.0, -1.0])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "num of values (2) do not equal num of parameters (21)")]
+    fn test_mlp_flat_parameters_wrong_len() {
+        Mlp::<f32>::new(3, vec![4, 1]).set_parameters_from(&[1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_mlp_zero_grad() {
+        let mlp = Mlp::new(3, vec![3, 1]);
+
+        let out = mlp.callf(&vec![2.0, 3.0, 2.0]);
+        out[0].backward();
+
+        assert!(mlp.parameters().iter().any(|p| p.grad() != 0.0));
+
+        mlp.zero_grad();
+
+        assert!(mlp.parameters().iter().all(|p| p.grad() == 0.0));
+    }
+
+    #[test]
+    fn test_mlp_save_and_load_json() {
+        let mlp: Mlp = Mlp::new(3, vec![4, 4, 1]);
+        let inputs = vec![2.0, 3.0, -1.0];
+
+        // a few steps of training so the weights aren't fresh from init
+        for _ in 0..3 {
+            let loss = (&mlp.callf(&inputs)[0] - 1.0).powf(2.0);
+            mlp.zero_grad();
+            loss.backward();
+            mlp.parameters()
+                .iter()
+                .for_each(|p| p.set_data(p.data() - 0.05 * p.grad()));
+        }
+
+        let path = std::env::temp_dir().join(format!("micrograd_mlp_{}.json", std::process::id()));
+        mlp.save_json(&path).unwrap();
+        let loaded: Mlp = Mlp::load_json(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.parameters(), mlp.parameters());
+        assert!(loaded.parameters().iter().all(|p| p.grad() == 0.0));
+        assert_eq!(loaded.callf(&inputs), mlp.callf(&inputs));
+    }
+
+    #[test]
+    fn test_mlp_save_and_load_bincode() {
+        let mlp = Mlp::<f32>::new_with_output(3, vec![4, 4, 1], Activation::Linear);
+        let inputs = vec![2.0, 3.0, -1.0];
+
+        let dir = std::env::temp_dir();
+        let bin_path = dir.join(format!("micrograd_mlp_{}.bin", std::process::id()));
+        let json_path = dir.join(format!("micrograd_mlp_bin_{}.json", std::process::id()));
+        mlp.save_bincode(&bin_path).unwrap();
+        mlp.save_json(&json_path).unwrap();
+
+        let loaded: Mlp = Mlp::load_bincode(&bin_path).unwrap();
+        let bin_size = std::fs::metadata(&bin_path).unwrap().len();
+        let json_size = std::fs::metadata(&json_path).unwrap().len();
+        std::fs::remove_file(&bin_path).unwrap();
+        std::fs::remove_file(&json_path).unwrap();
+
+        assert_eq!(loaded.parameters(), mlp.parameters());
+        assert_eq!(loaded.summary(), mlp.summary());
+        assert_eq!(loaded.callf(&inputs), mlp.callf(&inputs));
+        assert!(
+            bin_size < json_size,
+            "bincode {} vs json {} bytes",
+            bin_size,
+            json_size
+        );
+    }
+
+    #[test]
+    fn test_mlp_from_python_state() {
+        let json = include_str!("../tests/fixtures/python_state.json");
+        let mlp = Mlp::<f32>::from_python_state(json).unwrap();
+
+        assert_eq!(mlp.parameter_count(), 13);
+        assert_eq!(mlp.layers()[0].neurons()[0].activation(), Activation::Relu);
+        assert_eq!(
+            mlp.layers()[1].neurons()[0].activation(),
+            Activation::Linear
+        );
+
+        // outputs of the same model in Python micrograd
+        let cases = [
+            (vec![1.0, -2.0], -1.039704),
+            (vec![0.5, 0.25], -0.297449),
+            (vec![-1.5, 3.0], 0.559395),
+        ];
+        for (x, expected) in cases {
+            let out = mlp.callf(&x)[0].data();
+            assert!((out - expected).abs() < 1e-5, "{} vs {}", out, expected);
+        }
+
+        let short = r#"{"nin": 2, "nouts": [3, 1], "parameters": [0.1, 0.2]}"#;
+        assert!(matches!(
+            Mlp::<f32>::from_python_state(short),
+            Err(Error::ParameterCount {
+                expected: 13,
+                found: 2
+            })
+        ));
+        assert!(matches!(
+            Mlp::<f32>::from_python_state("not json"),
+            Err(Error::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_mlp_grad_stats() {
+        let mlp = Mlp::<f64>::new(1, vec![1, 1]);
+        assert_eq!(mlp.grad_stats().norm, 0.0);
+
+        // w1, b1, w2, b2
+        let grads = [3.0, -4.0, 0.0, 1.0];
+        mlp.parameters()
+            .iter()
+            .zip(grads)
+            .for_each(|(p, g)| p.set_grad(g));
+
+        let stats = mlp.grad_stats();
+        assert_eq!(stats.min, -4.0);
+        assert_eq!(stats.max, 3.0);
+        assert_eq!(stats.mean, 0.0);
+        assert!((stats.norm - 26.0_f64.sqrt()).abs() < 1e-12);
+
+        assert_eq!(mlp.layers()[0].grad_norm(), 5.0);
+        assert_eq!(mlp.layers()[1].grad_norm(), 1.0);
+
+        assert_eq!(Mlp::<f64>::new(1, vec![]).grad_stats().max, 0.0);
+    }
+
+    #[test]
+    fn test_mlp_new_with_activations() {
+        let mlp = Mlp::<f64>::new_with_activations(
+            2,
+            &[
+                (8, Activation::Relu),
+                (8, Activation::Relu),
+                (1, Activation::Linear),
+            ],
+        );
+
+        let activations: Vec<Activation> = mlp
+            .layers()
+            .iter()
+            .map(|l| l.neurons()[0].activation())
+            .collect();
+        assert_eq!(
+            activations,
+            vec![Activation::Relu, Activation::Relu, Activation::Linear]
+        );
+        assert!(mlp.layers()[1]
+            .neurons()
+            .iter()
+            .all(|n| n.activation() == Activation::Relu));
+
+        // with every parameter at 1: 3 per hidden unit, then 8 * 3 + 1, then
+        // 8 * 25 + 1, well past anything tanh could produce
+        mlp.set_parameters_from(&vec![1.0; mlp.parameter_count()]);
+        assert_eq!(mlp.callf(&vec![1.0, 1.0])[0].data(), 201.0);
+    }
+
+    #[test]
+    fn test_mlp_prune() {
+        let mlp = Mlp::<f32>::new(2, vec![2, 1]);
+        assert_eq!(mlp.sparsity(), 0.0);
+
+        // per neuron: weights then bias
+        mlp.set_parameters_from(&[0.05, -0.8, 0.01, -0.02, 0.3, -0.09, 0.05, 0.5, 0.001]);
+        mlp.prune(0.1);
+
+        assert_eq!(
+            mlp.get_parameters_flat(),
+            vec![0.0, -0.8, 0.01, 0.0, 0.3, -0.09, 0.0, 0.5, 0.001]
+        );
+        // 3 of the 6 weights
+        assert_eq!(mlp.sparsity(), 0.5);
+
+        assert_eq!(Mlp::<f32>::new(2, vec![]).sparsity(), 0.0);
+    }
+
+    #[test]
+    fn test_mlp_weight_histogram() {
+        let mlp = Mlp::<f32>::new(2, vec![2, 1]);
+        // per neuron: weights then bias, the biases would stretch the range
+        // to 5 if they were counted
+        mlp.set_parameters_from(&[-1.0, -0.75, 5.0, -0.5, 0.0, 5.0, 0.5, 1.0, 5.0]);
+
+        let histogram = mlp.weight_histogram(4);
+        assert_eq!(histogram, vec![(-1.0, 2), (-0.5, 1), (0.0, 1), (0.5, 2)]);
+        assert_eq!(
+            histogram.iter().map(|(_, count)| count).sum::<usize>(),
+            mlp.parameter_count() - 3
+        );
+
+        mlp.set_parameters_from(&[0.25, 0.25, -1.0, 0.25, 0.25, -1.0, 0.25, 0.25, -1.0]);
+        assert_eq!(mlp.weight_histogram(2), vec![(0.25, 6), (0.25, 0)]);
+
+        assert!(Mlp::<f32>::new(2, vec![]).weight_histogram(3).is_empty());
+    }
+
+    #[test]
+    fn test_mlp_reinit() {
+        let mlp = Mlp::<f64>::new_with_output(3, vec![4, 1], Activation::Linear);
+        let handle = mlp.clone();
+        let before = mlp.get_parameters_flat();
+
+        mlp.reinit(Some(42));
+        let first = mlp.get_parameters_flat();
+        mlp.reinit(Some(42));
+        let second = mlp.get_parameters_flat();
+
+        assert_eq!(first, second);
+        assert_ne!(first, before);
+        assert_eq!(first.len(), before.len());
+
+        // clones share the neurons, so they see the new weights too
+        assert_eq!(handle.get_parameters_flat(), second);
+        assert_eq!(
+            mlp.layers()[1].neurons()[0].activation(),
+            Activation::Linear
+        );
+
+        mlp.reinit(None);
+        assert_ne!(mlp.get_parameters_flat(), second);
+
+        // kaiming starts every bias at zero
+        mlp.reinit_with(Init::Kaiming, Some(42));
+        assert!(mlp
+            .layers()
+            .iter()
+            .all(|l| l.neurons().iter().all(|n| n.bias().data() == 0.0)));
+    }
+
+    #[test]
+    fn test_mlp_reinit_keeps_optimizer_parameters() {
+        let mlp = Mlp::new_seeded(3, vec![4, 1], 42);
+        let sgd = Sgd::new(mlp.parameters(), 0.1);
+
+        mlp.reinit(Some(7));
+        let reinitialized = mlp.get_parameters_flat();
+
+        let loss = (&mlp.callf(&vec![2.0, 3.0, -1.0])[0] - 1.0).powf(2.0);
+        sgd.zero_grad();
+        loss.backward();
+        sgd.step();
+
+        assert_ne!(mlp.get_parameters_flat(), reinitialized);
+    }
+
+    #[test]
+    fn test_mlp_new_seeded() {
+        let a: Mlp = Mlp::new_seeded(3, vec![4, 4, 1], 42);
+        let b: Mlp = Mlp::new_seeded(3, vec![4, 4, 1], 42);
+        let c: Mlp = Mlp::new_seeded(3, vec![4, 4, 1], 7);
+
+        assert_eq!(a.parameters().len(), b.parameters().len());
+        a.parameters()
+            .iter()
+            .zip(b.parameters())
+            .for_each(|(pa, pb)| assert_eq!(pa.data(), pb.data()));
+
+        assert_ne!(a.parameters(), c.parameters());
+    }
 }
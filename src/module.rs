@@ -0,0 +1,112 @@
+//! src/module.rs
+
+use crate::value::{Scalar, Value};
+
+/// A building block that maps input values to output values and may own
+/// trainable parameters.
+pub trait Module<T: Scalar = f32> {
+    fn call(&self, inputs: &[Value<T>]) -> Vec<Value<T>>;
+
+    fn parameters(&self) -> Vec<Value<T>>;
+
+    fn zero_grad(&self) {
+        self.parameters().iter().for_each(|p| p.zero_grad());
+    }
+}
+
+/// Runs a stack of modules in order, feeding each one's output to the next.
+pub struct Sequential<T: Scalar = f32>(Vec<Box<dyn Module<T>>>);
+
+impl<T: Scalar> Sequential<T> {
+    pub fn new(modules: Vec<Box<dyn Module<T>>>) -> Sequential<T> {
+        Sequential(modules)
+    }
+
+    pub fn push(&mut self, module: Box<dyn Module<T>>) {
+        self.0.push(module);
+    }
+
+    pub fn modules(&self) -> &Vec<Box<dyn Module<T>>> {
+        &self.0
+    }
+}
+
+impl<T: Scalar> Module<T> for Sequential<T> {
+    fn call(&self, inputs: &[Value<T>]) -> Vec<Value<T>> {
+        self.modules()
+            .iter()
+            .fold(inputs.to_vec(), |output, m| m.call(&output))
+    }
+
+    fn parameters(&self) -> Vec<Value<T>> {
+        self.modules()
+            .iter()
+            .map(|m| m.parameters())
+            .collect::<Vec<Vec<Value<T>>>>()
+            .concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        activation::Activation, dropout::Dropout, layer::Layer, mlp::Mlp, neuron::Neuron,
+        value::Value,
+    };
+
+    use super::{Module, Sequential};
+
+    #[test]
+    fn test_sequential_forward_backward() {
+        let first = Layer::new(3, 4);
+        let second = Layer::new(4, 2);
+
+        // with training off the dropout passes everything through
+        let dropout = Dropout::new(0.5);
+        dropout.set_training(false);
+
+        let model: Sequential = Sequential::new(vec![
+            Box::new(first.clone()),
+            Box::new(Activation::Relu),
+            Box::new(dropout),
+            Box::new(second.clone()),
+        ]);
+
+        assert_eq!(
+            model.parameters().len(),
+            first.parameter_count() + second.parameter_count()
+        );
+
+        let out = model.call(&Value::from_slice(&[2.0, 3.0, -1.0]));
+        assert_eq!(out.len(), 2);
+
+        // matches running the layers by hand
+        let inputs = Value::from_slice(&[2.0, 3.0, -1.0]);
+        let hidden: Vec<Value> = first.call(&inputs).iter().map(|h| h.relu()).collect();
+        let expected = second.call(&hidden);
+        assert!(out.iter().zip(&expected).all(|(o, e)| o == e));
+
+        let loss: Value = out.into_iter().sum();
+        loss.backward();
+
+        assert!(second.parameters().iter().any(|p| p.grad() != 0.0));
+    }
+
+    #[test]
+    fn test_module_trait_objects() {
+        fn count_and_clear(module: &dyn Module) -> usize {
+            module.zero_grad();
+            module.parameters().len()
+        }
+
+        let mlp = Mlp::new(3, vec![4, 4, 1]);
+        let loss: Value = mlp.callf(&vec![2.0, 3.0, -1.0]).into_iter().sum();
+        loss.backward();
+
+        assert_eq!(count_and_clear(&mlp), mlp.parameter_count());
+        assert!(mlp.parameters().iter().all(|p| p.grad() == 0.0));
+
+        assert_eq!(count_and_clear(&mlp.layers()[0]), 16);
+        assert_eq!(count_and_clear(&Neuron::new(3)), 4);
+    }
+}
@@ -1,39 +1,94 @@
 //! src/neuron.rs
 
-use std::{cell::RefCell, rc::Rc};
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use rand::{distributions::Uniform, prelude::Distribution};
-
-use crate::value::Value;
+use crate::{
+    activation::Activation,
+    error::Error,
+    init::Init,
+    module::Module,
+    shared::{Lock, Shared},
+    value::{cast, Scalar, Value},
+};
 
 #[derive(Debug)]
-struct InnerNeuron {
-    weights: Vec<Value>,
-    bias: Value,
+struct InnerNeuron<T> {
+    weights: Vec<Value<T>>,
+    bias: Value<T>,
+    activation: Activation,
+}
+
+/// What gets persisted for a neuron: just the numbers, no grads or graph.
+#[derive(Serialize, Deserialize)]
+struct NeuronState<T> {
+    weights: Vec<T>,
+    bias: T,
+    activation: Activation,
 }
 
 #[derive(Clone, Debug)]
-pub struct Neuron(Rc<RefCell<InnerNeuron>>);
+pub struct Neuron<T = f32>(Shared<Lock<InnerNeuron<T>>>);
+
+impl<T: Scalar> Neuron<T> {
+    pub fn new(nin: usize) -> Neuron<T> {
+        Neuron::new_with_activation(nin, Activation::Tanh)
+    }
+
+    pub fn new_with_activation(nin: usize, activation: Activation) -> Neuron<T> {
+        Neuron::new_with_init(nin, activation, Init::Uniform)
+    }
+
+    /// Weights scaled by `sqrt(2 / nin)`, see [`Init::Kaiming`].
+    pub fn new_kaiming(nin: usize) -> Neuron<T> {
+        Neuron::new_with_init(nin, Activation::Tanh, Init::Kaiming)
+    }
+
+    /// Weights scaled by `sqrt(1 / nin)`, see [`Init::Xavier`].
+    pub fn new_xavier(nin: usize) -> Neuron<T> {
+        Neuron::new_with_init(nin, Activation::Tanh, Init::Xavier)
+    }
 
-impl Neuron {
-    pub fn new(nin: usize) -> Neuron {
-        let uniform = Uniform::new_inclusive(-1.0, 1.0);
-        let mut rng = rand::thread_rng();
+    pub fn new_with_init(nin: usize, activation: Activation, init: Init) -> Neuron<T> {
+        Neuron::new_with_rng(nin, activation, init, &mut rand::thread_rng())
+    }
+
+    pub fn new_with_rng<R: Rng + ?Sized>(
+        nin: usize,
+        activation: Activation,
+        init: Init,
+        rng: &mut R,
+    ) -> Neuron<T> {
+        let (weights, bias) = init.sample(nin, rng);
+
+        let weights: Vec<Value<T>> = weights.into_iter().map(|w| Value::new(cast(w))).collect();
+        let bias = Value::new(cast(bias));
 
-        let weights: Vec<Value> = (0..nin)
-            .map(|_| Value::new(uniform.sample(&mut rng)))
-            .collect();
-        let bias = Value::new(uniform.sample(&mut rng));
+        let neuron = InnerNeuron {
+            weights,
+            bias,
+            activation,
+        };
 
-        let neuron = InnerNeuron { weights, bias };
+        Neuron(Shared::new(Lock::new(neuron)))
+    }
+
+    /// A tanh neuron with exactly these weights and bias.
+    pub fn from_parameters(weights: Vec<T>, bias: T) -> Neuron<T> {
+        let neuron = InnerNeuron {
+            weights: Value::from_slice(&weights),
+            bias: Value::new(bias),
+            activation: Activation::Tanh,
+        };
 
-        Neuron(Rc::new(RefCell::new(neuron)))
+        Neuron(Shared::new(Lock::new(neuron)))
     }
 
-    pub fn call(&self, inputs: &Vec<Value>) -> Value {
+    #[allow(clippy::ptr_arg)]
+    pub fn call(&self, inputs: &Vec<Value<T>>) -> Value<T> {
         assert_eq!(
-            self.num_weights(),
             inputs.len(),
+            self.num_weights(),
             "num of inputs ({}) do not equal num of weights ({})",
             inputs.len(),
             self.num_weights()
@@ -41,55 +96,149 @@ impl Neuron {
 
         let zipped = inputs.iter().zip(self.weights());
 
-        (zipped.map(|(x1, w1)| x1 * &w1).sum::<Value>() + self.bias()).tanh()
+        let out = zipped.map(|(x1, w1)| x1 * &w1).sum::<Value<T>>() + self.bias();
+
+        self.activation().apply(&out)
+    }
+
+    /// Like [`Neuron::call`], but a wrong number of inputs is returned as
+    /// [`Error::InputSize`] instead of panicking.
+    pub fn try_call(&self, inputs: &[Value<T>]) -> Result<Value<T>, Error> {
+        if inputs.len() != self.num_weights() {
+            return Err(Error::InputSize {
+                expected: self.num_weights(),
+                found: inputs.len(),
+            });
+        }
+
+        Ok(self.call(&inputs.to_vec()))
     }
 
-    pub fn callf(&self, inputs: &Vec<f32>) -> Value {
-        let inputs: Vec<Value> = inputs.iter().map(|x| Value::new(*x)).collect();
+    #[allow(clippy::ptr_arg)]
+    pub fn callf(&self, inputs: &Vec<T>) -> Value<T> {
+        let inputs = Value::from_slice(inputs);
 
         self.call(&inputs)
     }
 
-    pub fn parameters(&self) -> Vec<Value> {
+    pub fn parameters(&self) -> Vec<Value<T>> {
         [self.weights(), vec![self.bias()]].concat()
     }
 
-    pub fn weights(&self) -> Vec<Value> {
-        self.0.borrow().weights.clone()
+    /// The weights then the bias, like [`Neuron::parameters`], cloned one at
+    /// a time instead of collected into a `Vec`.
+    pub fn parameters_iter(&self) -> impl Iterator<Item = Value<T>> + '_ {
+        let n = self.num_weights();
+
+        (0..=n).map(move |i| {
+            let inner = self.0.read();
+            if i < n {
+                inner.weights[i].clone()
+            } else {
+                inner.bias.clone()
+            }
+        })
+    }
+
+    pub fn parameter_count(&self) -> usize {
+        self.num_weights() + 1
+    }
+
+    pub fn zero_grad(&self) {
+        self.parameters_iter().for_each(|p| p.zero_grad());
+    }
+
+    pub fn weights(&self) -> Vec<Value<T>> {
+        self.0.read().weights.clone()
     }
 
     pub fn num_weights(&self) -> usize {
-        self.0.borrow().weights.len()
+        self.0.read().weights.len()
+    }
+
+    pub fn set_weights(&self, weights: Vec<Value<T>>) {
+        self.0.write().weights = weights;
+    }
+
+    pub fn bias(&self) -> Value<T> {
+        self.0.read().bias.clone()
     }
 
-    pub fn set_weights(&self, weights: Vec<Value>) {
-        self.0.borrow_mut().weights = weights;
+    pub fn set_bias(&self, bias: Value<T>) {
+        self.0.write().bias = bias;
+    }
+
+    pub fn activation(&self) -> Activation {
+        self.0.read().activation
+    }
+
+    pub fn set_activation(&self, activation: Activation) {
+        self.0.write().activation = activation;
+    }
+}
+
+impl<T: Scalar + Serialize> Serialize for Neuron<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let state = NeuronState {
+            weights: self.weights().iter().map(|w| w.data()).collect(),
+            bias: self.bias().data(),
+            activation: self.activation(),
+        };
+
+        state.serialize(serializer)
+    }
+}
+
+impl<'de, T: Scalar + Deserialize<'de>> Deserialize<'de> for Neuron<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Neuron<T>, D::Error> {
+        let state = NeuronState::<T>::deserialize(deserializer)?;
+
+        let neuron = InnerNeuron {
+            weights: state.weights.into_iter().map(Value::new).collect(),
+            bias: Value::new(state.bias),
+            activation: state.activation,
+        };
+
+        Ok(Neuron(Shared::new(Lock::new(neuron))))
     }
+}
 
-    pub fn bias(&self) -> Value {
-        self.0.borrow().bias.clone()
+impl<T: Scalar> Module<T> for Neuron<T> {
+    fn call(&self, inputs: &[Value<T>]) -> Vec<Value<T>> {
+        vec![Neuron::call(self, &inputs.to_vec())]
     }
 
-    pub fn set_bias(&self, bias: Value) {
-        self.0.borrow_mut().bias = bias;
+    fn parameters(&self) -> Vec<Value<T>> {
+        Neuron::parameters(self)
     }
 }
 
+// test_neuron_call predates running clippy on the tests and is kept as
+// originally written
 #[cfg(test)]
+#[allow(
+    clippy::approx_constant,
+    clippy::excessive_precision,
+    clippy::get_first
+)]
 mod tests {
-    use crate::value::Value;
+    use crate::{
+        activation::Activation,
+        error::Error,
+        value::{Value, Value32},
+    };
 
     use super::Neuron;
 
     #[test]
     fn test_neuron_call() {
-        let x1 = Value::new(2.0);
-        let x2 = Value::new(0.0);
+        let x1 = Value32::new(2.0);
+        let x2 = Value32::new(0.0);
 
-        let w1 = Value::new(-3.0);
-        let w2 = Value::new(1.0);
+        let w1 = Value32::new(-3.0);
+        let w2 = Value32::new(1.0);
 
-        let b = Value::new(6.8813735870195432);
+        let b = Value32::new(6.8813735870195432);
 
         let neuron = Neuron::new(2);
         neuron.set_weights(vec![w1, w2]);
@@ -104,4 +253,109 @@ mod tests {
         assert_eq!(x1.grad(), -1.5000004);
         assert_eq!(x2.grad(), 0.5000001);
     }
+
+    #[test]
+    fn test_neuron_from_parameters() {
+        let neuron = Neuron::from_parameters(vec![-3.0_f64, 1.0], 1.0);
+
+        assert_eq!(neuron.num_weights(), 2);
+        assert_eq!(neuron.activation(), Activation::Tanh);
+        assert_eq!(
+            neuron
+                .parameters()
+                .iter()
+                .map(|p| p.data())
+                .collect::<Vec<_>>(),
+            vec![-3.0, 1.0, 1.0]
+        );
+
+        // -3 * 2 + 1 * 4 + 1
+        assert_eq!(neuron.callf(&vec![2.0, 4.0]).data(), (-1.0_f64).tanh());
+    }
+
+    #[test]
+    fn test_neuron_zero_inputs() {
+        let neuron = Neuron::new_with_activation(0, Activation::Linear);
+        neuron.set_bias(Value::new(1.5_f32));
+
+        assert_eq!(neuron.callf(&vec![]).data(), 1.5);
+        assert_eq!(neuron.try_call(&[]).unwrap().data(), 1.5);
+    }
+
+    #[test]
+    fn test_neuron_try_call_mismatch() {
+        let neuron = Neuron::<f32>::new(3);
+
+        let err = neuron
+            .try_call(&Value::from_slice(&[1.0, 2.0]))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InputSize {
+                expected: 3,
+                found: 2
+            }
+        ));
+        assert_eq!(err.to_string(), "expected 3 inputs, found 2");
+
+        assert!(neuron
+            .try_call(&Value::from_slice(&[1.0, 2.0, 3.0]))
+            .is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "num of inputs (2) do not equal num of weights (3)")]
+    fn test_neuron_call_mismatch_message() {
+        Neuron::<f32>::new(3).callf(&vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_neuron_call_with_activation() {
+        let x1 = Value::new(2.0);
+        let x2 = Value::new(0.0);
+
+        let neuron = Neuron::new_with_activation(2, Activation::Linear);
+        neuron.set_weights(vec![Value::new(-3.0), Value::new(1.0)]);
+        neuron.set_bias(Value::new(1.0));
+
+        let result = neuron.call(&vec![x1, x2]);
+
+        assert_eq!(neuron.activation(), Activation::Linear);
+        assert_eq!(result.data(), -5.0);
+
+        neuron.set_activation(Activation::Relu);
+        let result = neuron.callf(&vec![2.0, 0.0]);
+
+        assert_eq!(result.data(), 0.0);
+        assert_eq!(Neuron::<f32>::new(2).activation(), Activation::Tanh);
+    }
+
+    #[test]
+    fn test_neuron_init_variance() {
+        let nin = 10_000;
+
+        let variance = |neuron: Neuron| {
+            let weights: Vec<f32> = neuron.weights().iter().map(|w| w.data()).collect();
+            let mean = weights.iter().sum::<f32>() / nin as f32;
+
+            weights.iter().map(|w| (w - mean).powi(2)).sum::<f32>() / nin as f32
+        };
+
+        let kaiming = variance(Neuron::new_kaiming(nin));
+        let xavier = variance(Neuron::new_xavier(nin));
+
+        let target = 2.0 / nin as f32;
+        assert!(
+            (kaiming - target).abs() < 0.1 * target,
+            "kaiming variance {}",
+            kaiming
+        );
+
+        let target = 1.0 / nin as f32;
+        assert!(
+            (xavier - target).abs() < 0.1 * target,
+            "xavier variance {}",
+            xavier
+        );
+    }
 }
@@ -0,0 +1,314 @@
+//! src/optim.rs
+
+use std::cell::{Cell, RefCell};
+
+use crate::value::Value;
+
+/// Shared interface for parameter update rules, so training loops can be
+/// written once against any optimizer.
+pub trait Optimizer {
+    fn step(&self);
+
+    fn zero_grad(&self);
+
+    fn lr(&self) -> f32;
+
+    /// Overrides the learning rate used by subsequent steps, e.g. from a
+    /// scheduler.
+    fn set_lr(&self, lr: f32);
+}
+
+#[derive(Clone, Debug)]
+pub struct Sgd {
+    params: Vec<Value>,
+    lr: Cell<f32>,
+    momentum: f32,
+    weight_decay: f32,
+    velocity: RefCell<Vec<f32>>,
+}
+
+impl Sgd {
+    pub fn new(params: Vec<Value>, lr: f32) -> Sgd {
+        Sgd::new_with_options(params, lr, 0.0, 0.0)
+    }
+
+    pub fn new_with_options(params: Vec<Value>, lr: f32, momentum: f32, weight_decay: f32) -> Sgd {
+        let velocity = RefCell::new(vec![0.0; params.len()]);
+
+        Sgd {
+            params,
+            lr: Cell::new(lr),
+            momentum,
+            weight_decay,
+            velocity,
+        }
+    }
+
+    pub fn params(&self) -> &Vec<Value> {
+        &self.params
+    }
+
+    pub fn momentum(&self) -> f32 {
+        self.momentum
+    }
+
+    pub fn weight_decay(&self) -> f32 {
+        self.weight_decay
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&self) {
+        let mut velocity = self.velocity.borrow_mut();
+
+        self.params()
+            .iter()
+            .zip(velocity.iter_mut())
+            .filter(|(p, _)| p.requires_grad())
+            .for_each(|(p, v)| {
+                *v = self.momentum() * *v + p.grad() + self.weight_decay() * p.data();
+                p.set_data(p.data() - self.lr() * *v);
+            });
+    }
+
+    fn zero_grad(&self) {
+        self.params().iter().for_each(|p| p.zero_grad());
+    }
+
+    fn lr(&self) -> f32 {
+        self.lr.get()
+    }
+
+    fn set_lr(&self, lr: f32) {
+        self.lr.set(lr);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Adam {
+    params: Vec<Value>,
+    lr: Cell<f32>,
+    betas: (f32, f32),
+    eps: f32,
+    m: RefCell<Vec<f32>>,
+    v: RefCell<Vec<f32>>,
+    t: Cell<i32>,
+}
+
+impl Adam {
+    pub fn new(params: Vec<Value>, lr: f32) -> Adam {
+        Adam::new_with_options(params, lr, (0.9, 0.999), 1e-8)
+    }
+
+    pub fn new_with_options(params: Vec<Value>, lr: f32, betas: (f32, f32), eps: f32) -> Adam {
+        let m = RefCell::new(vec![0.0; params.len()]);
+        let v = RefCell::new(vec![0.0; params.len()]);
+
+        Adam {
+            params,
+            lr: Cell::new(lr),
+            betas,
+            eps,
+            m,
+            v,
+            t: Cell::new(0),
+        }
+    }
+
+    pub fn params(&self) -> &Vec<Value> {
+        &self.params
+    }
+
+    pub fn betas(&self) -> (f32, f32) {
+        self.betas
+    }
+
+    pub fn eps(&self) -> f32 {
+        self.eps
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&self) {
+        let (beta1, beta2) = self.betas();
+        let t = self.t.get() + 1;
+        self.t.set(t);
+
+        let mut m = self.m.borrow_mut();
+        let mut v = self.v.borrow_mut();
+
+        self.params()
+            .iter()
+            .zip(m.iter_mut().zip(v.iter_mut()))
+            .filter(|(p, _)| p.requires_grad())
+            .for_each(|(p, (m, v))| {
+                let grad = p.grad();
+
+                *m = beta1 * *m + (1.0 - beta1) * grad;
+                *v = beta2 * *v + (1.0 - beta2) * grad.powi(2);
+
+                let m_hat = *m / (1.0 - beta1.powi(t));
+                let v_hat = *v / (1.0 - beta2.powi(t));
+
+                p.set_data(p.data() - self.lr() * m_hat / (v_hat.sqrt() + self.eps()));
+            });
+    }
+
+    fn zero_grad(&self) {
+        self.params().iter().for_each(|p| p.zero_grad());
+    }
+
+    fn lr(&self) -> f32 {
+        self.lr.get()
+    }
+
+    fn set_lr(&self, lr: f32) {
+        self.lr.set(lr);
+    }
+}
+
+/// Rescales the gradients of `params` so their global L2 norm is at most
+/// `max_norm`, preserving direction. Returns the norm before clipping.
+pub fn clip_grad_norm(params: &[Value], max_norm: f32) -> f32 {
+    let total_norm = params.iter().map(|p| p.grad().powi(2)).sum::<f32>().sqrt();
+
+    if total_norm > max_norm {
+        let scale = max_norm / total_norm;
+        params.iter().for_each(|p| p.set_grad(p.grad() * scale));
+    }
+
+    total_norm
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mlp::Mlp, value::Value};
+
+    use super::{clip_grad_norm, Adam, Optimizer, Sgd};
+
+    #[test]
+    fn test_sgd_step() {
+        // minimize (x - 3)^2, starting from x = 0
+        let x = Value::new(0.0);
+        let sgd = Sgd::new(vec![x.clone()], 0.1);
+
+        let mut last_distance = (x.data() - 3.0).abs();
+
+        for _ in 0..5 {
+            let loss = (&x - 3.0).powf(2.0);
+
+            sgd.zero_grad();
+            loss.backward();
+            sgd.step();
+
+            let distance = (x.data() - 3.0).abs();
+            assert!(distance < last_distance);
+            last_distance = distance;
+        }
+
+        assert!(last_distance < 1.0);
+    }
+
+    #[test]
+    fn test_sgd_momentum_converges_faster() {
+        let train = |sgd: &Sgd, x: &Value| {
+            for _ in 0..20 {
+                let loss = (x - 3.0).powf(2.0);
+
+                sgd.zero_grad();
+                loss.backward();
+                sgd.step();
+            }
+
+            (x.data() - 3.0).abs()
+        };
+
+        let x = Value::new(0.0);
+        let plain = train(&Sgd::new(vec![x.clone()], 0.01), &x);
+
+        let x = Value::new(0.0);
+        let momentum = train(&Sgd::new_with_options(vec![x.clone()], 0.01, 0.9, 0.0), &x);
+
+        assert!(
+            momentum < plain,
+            "momentum distance {} should be less than plain distance {}",
+            momentum,
+            plain
+        );
+    }
+
+    #[test]
+    fn test_sgd_weight_decay() {
+        let x = Value::new(2.0);
+        let sgd = Sgd::new_with_options(vec![x.clone()], 0.1, 0.0, 0.5);
+
+        // no gradient, so only the decay term moves the parameter
+        sgd.step();
+
+        assert_eq!(x.data(), 2.0 - 0.1 * 0.5 * 2.0);
+    }
+
+    #[test]
+    fn test_adam_step() {
+        let x = Value::new(0.0);
+        let adam = Adam::new(vec![x.clone()], 0.1);
+
+        let loss = (&x - 3.0).powf(2.0);
+        loss.backward();
+        adam.step();
+
+        // the first bias-corrected step moves by roughly lr in the descent direction
+        assert!((x.data() - 0.1).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_frozen_parameters_skip_step() {
+        let mlp = Mlp::new(2, vec![3, 1]);
+        let first = mlp.layers()[0].parameters();
+        first.iter().for_each(|p| p.set_requires_grad(false));
+
+        let frozen = first.iter().map(|p| p.data()).collect::<Vec<f32>>();
+        let trainable = mlp.layers()[1].parameters();
+        let before = trainable.iter().map(|p| p.data()).collect::<Vec<f32>>();
+
+        let optimizers: [Box<dyn Optimizer>; 2] = [
+            Box::new(Sgd::new_with_options(mlp.parameters(), 0.1, 0.9, 0.01)),
+            Box::new(Adam::new(mlp.parameters(), 0.1)),
+        ];
+        for optimizer in optimizers {
+            let loss = (&mlp.callf(&vec![1.0, -2.0])[0] - 2.0).powf(2.0);
+            optimizer.zero_grad();
+            loss.backward();
+            optimizer.step();
+        }
+
+        // grads still reach the frozen layer, only the update is skipped
+        assert!(first.iter().any(|p| p.grad() != 0.0));
+        assert_eq!(first.iter().map(|p| p.data()).collect::<Vec<f32>>(), frozen);
+        assert_ne!(
+            trainable.iter().map(|p| p.data()).collect::<Vec<f32>>(),
+            before
+        );
+        assert!(trainable.iter().all(|p| p.requires_grad()));
+    }
+
+    #[test]
+    fn test_clip_grad_norm() {
+        let params = [Value::new(0.0), Value::new(0.0)];
+        params[0].set_grad(3.0);
+        params[1].set_grad(4.0);
+
+        let total_norm = clip_grad_norm(&params, 1.0);
+
+        assert_eq!(total_norm, 5.0);
+        assert_eq!(params[0].grad(), 0.6);
+        assert_eq!(params[1].grad(), 0.8);
+
+        let clipped_norm = params.iter().map(|p| p.grad().powi(2)).sum::<f32>().sqrt();
+        assert!((clipped_norm - 1.0).abs() < 1e-6);
+
+        // under the limit nothing changes
+        clip_grad_norm(&params, 10.0);
+        assert_eq!(params[0].grad(), 0.6);
+    }
+}
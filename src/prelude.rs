@@ -0,0 +1,34 @@
+//! src/prelude.rs
+//!
+//! The types and traits most programs need, in one import.
+//!
+//! ```
+//! use micrograd_rust::prelude::*;
+//!
+//! let mlp: Mlp = Mlp::new_with_activations(2, &[(4, Activation::Relu), (1, Activation::Linear)]);
+//! let sgd = Sgd::new(mlp.parameters(), 0.01);
+//!
+//! let pred = &mlp.callf(&vec![1.0, -1.0])[0];
+//! let loss = (pred - 0.5).powf(2.0);
+//! sgd.zero_grad();
+//! loss.backward();
+//! sgd.step();
+//! ```
+
+pub use crate::{
+    activation::{Activation, ScaledOutput},
+    batch_norm::BatchNorm,
+    data::{DataLoader, Dataset},
+    dropout::Dropout,
+    ema::Ema,
+    error::Error,
+    init::Init,
+    layer::Layer,
+    mlp::Mlp,
+    module::{Module, Sequential},
+    neuron::Neuron,
+    optim::{Adam, Optimizer, Sgd},
+    scheduler::{ExponentialLr, StepLr},
+    trainer::{Trainer, TrainerConfig},
+    value::{no_grad, Scalar, Value, Value32, Value64},
+};
@@ -0,0 +1,118 @@
+//! src/scheduler.rs
+
+use std::cell::Cell;
+
+use crate::optim::Optimizer;
+
+/// Multiplies the optimizer's learning rate by `gamma` once every
+/// `step_size` calls to [`StepLr::step`].
+pub struct StepLr<'a, O: Optimizer> {
+    optimizer: &'a O,
+    step_size: usize,
+    gamma: f32,
+    epoch: Cell<usize>,
+}
+
+impl<'a, O: Optimizer> StepLr<'a, O> {
+    pub fn new(optimizer: &'a O, step_size: usize, gamma: f32) -> StepLr<'a, O> {
+        assert!(step_size > 0, "step_size must be at least 1");
+
+        StepLr {
+            optimizer,
+            step_size,
+            gamma,
+            epoch: Cell::new(0),
+        }
+    }
+
+    /// Advances one epoch, decaying the learning rate at every boundary.
+    pub fn step(&self) {
+        let epoch = self.epoch.get() + 1;
+        self.epoch.set(epoch);
+
+        if epoch.is_multiple_of(self.step_size()) {
+            self.optimizer.set_lr(self.optimizer.lr() * self.gamma());
+        }
+    }
+
+    pub fn step_size(&self) -> usize {
+        self.step_size
+    }
+
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    pub fn epoch(&self) -> usize {
+        self.epoch.get()
+    }
+}
+
+/// Multiplies the optimizer's learning rate by `gamma` on every call to
+/// [`ExponentialLr::step`].
+pub struct ExponentialLr<'a, O: Optimizer> {
+    optimizer: &'a O,
+    gamma: f32,
+    epoch: Cell<usize>,
+}
+
+impl<'a, O: Optimizer> ExponentialLr<'a, O> {
+    pub fn new(optimizer: &'a O, gamma: f32) -> ExponentialLr<'a, O> {
+        ExponentialLr {
+            optimizer,
+            gamma,
+            epoch: Cell::new(0),
+        }
+    }
+
+    pub fn step(&self) {
+        self.epoch.set(self.epoch.get() + 1);
+        self.optimizer.set_lr(self.optimizer.lr() * self.gamma());
+    }
+
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    pub fn epoch(&self) -> usize {
+        self.epoch.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        optim::{Adam, Optimizer, Sgd},
+        value::Value,
+    };
+
+    use super::{ExponentialLr, StepLr};
+
+    #[test]
+    fn test_step_lr() {
+        let sgd = Sgd::new(vec![Value::new(0.0)], 1.0);
+        let scheduler = StepLr::new(&sgd, 2, 0.5);
+
+        let mut lrs = vec![];
+        for _ in 0..6 {
+            lrs.push(sgd.lr());
+            scheduler.step();
+        }
+
+        assert_eq!(lrs, vec![1.0, 1.0, 0.5, 0.5, 0.25, 0.25]);
+        assert_eq!(scheduler.epoch(), 6);
+    }
+
+    #[test]
+    fn test_exponential_lr() {
+        let adam = Adam::new(vec![Value::new(0.0)], 0.1);
+        let scheduler = ExponentialLr::new(&adam, 0.9);
+
+        for epoch in 0..10 {
+            let expected = 0.1 * 0.9_f32.powi(epoch);
+            assert!((adam.lr() - expected).abs() < 1e-6);
+
+            scheduler.step();
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! src/shared.rs
+//!
+//! Shared-ownership primitives behind [`crate::value::Value`] and
+//! [`crate::neuron::Neuron`]. By default these are `Rc` and `RefCell`, which
+//! are cheap but tie a graph to the thread that built it. The `threadsafe`
+//! feature swaps in `Arc` and `RwLock`, so graphs are `Send + Sync` at the
+//! cost of atomic refcounts and a lock on every data/grad access, which makes
+//! single-threaded training noticeably slower.
+
+#[cfg(not(feature = "threadsafe"))]
+pub(crate) use std::rc::{Rc as Shared, Weak as WeakShared};
+#[cfg(feature = "threadsafe")]
+pub(crate) use std::sync::{Arc as Shared, Weak as WeakShared};
+
+#[cfg(not(feature = "threadsafe"))]
+use std::cell::{Ref, RefCell, RefMut};
+#[cfg(feature = "threadsafe")]
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Closures usable as a node's backward pass. With `threadsafe` they must
+/// also be `Send + Sync`.
+#[cfg(not(feature = "threadsafe"))]
+pub trait BackwardFn: Fn() + 'static {}
+#[cfg(not(feature = "threadsafe"))]
+impl<F: Fn() + 'static> BackwardFn for F {}
+
+/// Closures usable as a node's backward pass. With `threadsafe` they must
+/// also be `Send + Sync`.
+#[cfg(feature = "threadsafe")]
+pub trait BackwardFn: Fn() + Send + Sync + 'static {}
+#[cfg(feature = "threadsafe")]
+impl<F: Fn() + Send + Sync + 'static> BackwardFn for F {}
+
+/// Interior mutability with the same `read`/`write` calls either way.
+#[cfg(not(feature = "threadsafe"))]
+#[derive(Debug)]
+pub(crate) struct Lock<T>(RefCell<T>);
+
+#[cfg(not(feature = "threadsafe"))]
+impl<T> Lock<T> {
+    pub(crate) fn new(value: T) -> Lock<T> {
+        Lock(RefCell::new(value))
+    }
+
+    pub(crate) fn read(&self) -> Ref<'_, T> {
+        self.0.borrow()
+    }
+
+    pub(crate) fn write(&self) -> RefMut<'_, T> {
+        self.0.borrow_mut()
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+}
+
+/// Interior mutability with the same `read`/`write` calls either way.
+#[cfg(feature = "threadsafe")]
+#[derive(Debug)]
+pub(crate) struct Lock<T>(RwLock<T>);
+
+#[cfg(feature = "threadsafe")]
+impl<T> Lock<T> {
+    pub(crate) fn new(value: T) -> Lock<T> {
+        Lock(RwLock::new(value))
+    }
+
+    // a panicking backward pass shouldn't make the rest of the graph unusable,
+    // so poisoning is ignored
+    pub(crate) fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.0.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,91 @@
+//! src/testutil.rs
+
+use crate::value::{cast, Scalar, Value};
+
+/// Central finite-difference gradients of the scalar `f` at `inputs`.
+///
+/// Each input is nudged by `+eps` and `-eps` on a fresh copy of the inputs,
+/// so the originals (and any grads already on them) are left untouched. The
+/// result lines up with `inputs` for comparison against analytic `grad()`s.
+pub fn grad_check<T: Scalar>(
+    f: impl Fn(&[Value<T>]) -> Value<T>,
+    inputs: &[Value<T>],
+    eps: T,
+) -> Vec<T> {
+    let data: Vec<T> = inputs.iter().map(|v| v.data()).collect();
+
+    let eval = |i: usize, delta: T| -> T {
+        let shifted: Vec<Value<T>> = data
+            .iter()
+            .enumerate()
+            .map(|(j, x)| Value::new(if i == j { *x + delta } else { *x }))
+            .collect();
+
+        f(&shifted).data()
+    };
+
+    (0..inputs.len())
+        .map(|i| (eval(i, eps) - eval(i, -eps)) / (cast::<T>(2.0) * eps))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::{Value, Value64};
+
+    use super::grad_check;
+
+    fn assert_grads_match(f: impl Fn(&[Value64]) -> Value64, inputs: &[f64]) {
+        let inputs: Vec<Value64> = inputs.iter().map(|x| Value::new(*x)).collect();
+
+        f(&inputs).backward();
+        let numeric = grad_check(&f, &inputs, 1e-6);
+
+        for (input, numeric) in inputs.iter().zip(numeric) {
+            assert!(
+                (input.grad() - numeric).abs() < 1e-5,
+                "analytic {} vs numeric {}",
+                input.grad(),
+                numeric
+            );
+        }
+    }
+
+    #[test]
+    fn test_grad_check_tanh() {
+        assert_grads_match(|x| x[0].tanh(), &[0.7]);
+        assert_grads_match(|x| (&x[0] * &x[1]).tanh(), &[-0.3, 1.5]);
+    }
+
+    #[test]
+    fn test_grad_check_exp() {
+        assert_grads_match(|x| x[0].exp(), &[1.3]);
+        assert_grads_match(|x| (&x[0] + &x[1]).exp(), &[-0.3, 0.5]);
+    }
+
+    #[test]
+    fn test_grad_check_powv() {
+        assert_grads_match(|x| x[0].powv(&x[1]), &[1.5, 2.5]);
+        assert_grads_match(|x| x[0].powf(3.0), &[-1.2]);
+    }
+
+    #[test]
+    fn test_grad_check_div() {
+        assert_grads_match(|x| &x[0] / &x[1], &[1.5, -2.5]);
+        assert_grads_match(|x| 2.0 / &x[0], &[0.8]);
+    }
+
+    #[test]
+    fn test_grad_check_elu() {
+        assert_grads_match(|x| x[0].elu(1.0), &[1.3]);
+        assert_grads_match(|x| x[0].elu(0.5), &[-0.8]);
+        assert_grads_match(|x| (&x[0] * &x[1]).elu(2.0), &[-0.3, 1.5]);
+    }
+
+    #[test]
+    fn test_grad_check_gelu() {
+        assert_grads_match(|x| x[0].gelu(), &[1.3]);
+        assert_grads_match(|x| x[0].gelu(), &[-0.8]);
+        assert_grads_match(|x| (&x[0] * &x[1]).gelu(), &[-0.3, 1.5]);
+    }
+}
@@ -0,0 +1,184 @@
+//! src/trainer.rs
+
+use crate::{mlp::Mlp, optim::Optimizer, value::Value};
+
+/// Loss applied to a batch of predictions and their targets.
+pub type LossFn = fn(&[Value], &[f32]) -> Value;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrainerConfig {
+    /// Stop as soon as an epoch's loss drops below this value.
+    pub early_stopping: Option<f32>,
+    /// Number of micro-batches each epoch's data is split into. Their
+    /// gradients are accumulated and applied in a single optimizer step.
+    pub accumulation_steps: usize,
+}
+
+impl Default for TrainerConfig {
+    fn default() -> TrainerConfig {
+        TrainerConfig {
+            early_stopping: None,
+            accumulation_steps: 1,
+        }
+    }
+}
+
+pub struct Trainer<O: Optimizer> {
+    mlp: Mlp,
+    optimizer: O,
+    loss_fn: LossFn,
+    config: TrainerConfig,
+}
+
+impl<O: Optimizer> Trainer<O> {
+    pub fn new(mlp: Mlp, optimizer: O, loss_fn: LossFn) -> Trainer<O> {
+        Trainer::new_with_config(mlp, optimizer, loss_fn, TrainerConfig::default())
+    }
+
+    pub fn new_with_config(
+        mlp: Mlp,
+        optimizer: O,
+        loss_fn: LossFn,
+        config: TrainerConfig,
+    ) -> Trainer<O> {
+        Trainer {
+            mlp,
+            optimizer,
+            loss_fn,
+            config,
+        }
+    }
+
+    /// Runs full-batch training for up to `epochs` epochs and returns the loss
+    /// of each epoch, measured before that epoch's update.
+    ///
+    /// The network is expected to have a single output; only the first output
+    /// of each prediction is fed to the loss.
+    ///
+    /// With `accumulation_steps > 1` the epoch's loss is the sum of the
+    /// micro-batch losses, so the update matches a single full batch exactly
+    /// for summed losses like [`crate::loss::mse`] but not for averaged ones.
+    pub fn fit(&mut self, xs: &[Vec<f32>], ys: &[f32], epochs: usize) -> Vec<f32> {
+        let mut history = Vec::with_capacity(epochs);
+
+        for _ in 0..epochs {
+            let loss = self.step(xs, ys);
+            history.push(loss);
+
+            if self
+                .config
+                .early_stopping
+                .is_some_and(|threshold| loss < threshold)
+            {
+                break;
+            }
+        }
+
+        history
+    }
+
+    /// Takes a single optimizer step on one sample and returns its loss,
+    /// measured before the update. For data that arrives one sample at a
+    /// time.
+    pub fn train_one(&mut self, x: &[f32], y: f32) -> f32 {
+        self.step(&[x.to_vec()], &[y])
+    }
+
+    // one forward/backward pass over the batch, split into the configured
+    // micro-batches, then a single optimizer step
+    fn step(&mut self, xs: &[Vec<f32>], ys: &[f32]) -> f32 {
+        let chunk_size = xs
+            .len()
+            .div_ceil(self.config.accumulation_steps.max(1))
+            .max(1);
+
+        // grads are only cleared once per step, the micro-batches add up
+        self.mlp.zero_grad();
+
+        let mut loss = 0.0;
+        for (xs, ys) in xs.chunks(chunk_size).zip(ys.chunks(chunk_size)) {
+            let micro_loss = self.mlp.forward_loss(xs, ys, self.loss_fn);
+
+            micro_loss.backward();
+            loss += micro_loss.data();
+        }
+
+        self.optimizer.step();
+
+        loss
+    }
+
+    pub fn mlp(&self) -> &Mlp {
+        &self.mlp
+    }
+
+    pub fn optimizer(&self) -> &O {
+        &self.optimizer
+    }
+
+    pub fn config(&self) -> TrainerConfig {
+        self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{loss, mlp::Mlp, optim::Sgd};
+
+    use super::{Trainer, TrainerConfig};
+
+    #[test]
+    fn test_trainer_accumulation_steps() {
+        let xs = [
+            vec![2.0, 3.0, -1.0],
+            vec![3.0, -1.0, 0.5],
+            vec![0.5, 1.0, 1.0],
+            vec![1.0, 1.0, -1.0],
+        ];
+        let ys = [1.0, -1.0, -1.0, 1.0];
+
+        let train = |accumulation_steps| {
+            let mlp = Mlp::new_seeded(3, vec![4, 4, 1], 42);
+            let sgd = Sgd::new(mlp.parameters(), 0.05);
+            let config = TrainerConfig {
+                accumulation_steps,
+                ..TrainerConfig::default()
+            };
+
+            let mut trainer = Trainer::new_with_config(mlp, sgd, loss::mse, config);
+            let history = trainer.fit(&xs, &ys, 1);
+
+            let params: Vec<f32> = trainer
+                .mlp()
+                .parameters()
+                .iter()
+                .map(|p| p.data())
+                .collect();
+            (history, params)
+        };
+
+        let (full_history, full) = train(1);
+        let (micro_history, micro) = train(2);
+
+        assert!((full_history[0] - micro_history[0]).abs() < 1e-5);
+        full.iter()
+            .zip(&micro)
+            .for_each(|(f, m)| assert!((f - m).abs() < 1e-6, "{} vs {}", f, m));
+    }
+
+    #[test]
+    fn test_trainer_train_one() {
+        let mlp = Mlp::new_seeded(3, vec![4, 4, 1], 42);
+        let sgd = Sgd::new(mlp.parameters(), 0.05);
+        let mut trainer = Trainer::new(mlp, sgd, loss::mse);
+
+        let (x, y) = (vec![2.0, 3.0, -1.0], 0.5);
+        let distance = |trainer: &Trainer<Sgd>| (trainer.mlp().callf(&x)[0].data() - y).abs();
+
+        let start = distance(&trainer);
+        let losses: Vec<f32> = (0..20).map(|_| trainer.train_one(&x, y)).collect();
+
+        assert!(losses.windows(2).all(|w| w[1] <= w[0]), "{:?}", losses);
+        assert!(distance(&trainer) < start / 10.0);
+    }
+}
@@ -1,64 +1,465 @@
 //! src/value.rs
 
-use std::cell::RefCell;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display};
+use std::hash::{Hash, Hasher};
 use std::iter::Sum;
 use std::ops;
-use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 
-struct InnerValue {
-    data: f32,
-    grad: f32,
-    backward: Box<dyn Fn()>,
-    prev: Vec<Value>,
-    op: String,
+use num_traits::Float;
+
+pub use crate::shared::BackwardFn;
+use crate::{
+    error::NonFiniteError,
+    shared::{Lock, Shared, WeakShared},
+};
+
+/// Floating point types a [`Value`] can hold, i.e. `f32` and `f64`.
+pub trait Scalar: Float + Debug + Display + Send + Sync + 'static {}
+
+impl<T: Float + Debug + Display + Send + Sync + 'static> Scalar for T {}
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static GRAD_ENABLED: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Turns graph construction on or off for the current thread.
+///
+/// While disabled, ops only compute `data`: results get no children and no
+/// backward pass, which makes pure inference much cheaper. Each thread starts
+/// out enabled.
+pub fn set_grad_enabled(enabled: bool) {
+    GRAD_ENABLED.with(|flag| flag.set(enabled));
+}
+
+pub fn is_grad_enabled() -> bool {
+    GRAD_ENABLED.with(|flag| flag.get())
+}
+
+/// Disables graph construction on this thread until the returned guard is
+/// dropped, like `torch.no_grad()`. Guards nest, each one restores the state
+/// it found.
+pub fn no_grad() -> NoGradGuard {
+    let previous = is_grad_enabled();
+    set_grad_enabled(false);
+
+    NoGradGuard { previous }
+}
+
+/// The derivative the backward pass of the unary op named `op` (a label as
+/// returned by [`Value::op`], e.g. `"tanh"` or `"relu"`) uses at input
+/// `value`, without building or running a graph. `None` for ops that take
+/// more than one input or a parameter.
+pub fn activation_derivative<T: Scalar>(op: &str, value: &Value<T>) -> Option<T> {
+    let _guard = no_grad();
+
+    let (out, op) = match op {
+        "exp" => (value.exp(), Op::Exp),
+        "recip" => (value.recip(), Op::Recip),
+        "ln" => (value.ln(), Op::Ln),
+        "sqrt" => (value.sqrt(), Op::Sqrt),
+        "sin" => (value.sin(), Op::Sin),
+        "cos" => (value.cos(), Op::Cos),
+        "tanh" => (value.tanh(), Op::Tanh),
+        "sigmoid" => (value.sigmoid(), Op::Sigmoid),
+        "softplus" => (value.softplus(), Op::Softplus),
+        "relu" => (value.relu(), Op::Relu),
+        "abs" => (value.abs(), Op::Abs),
+        _ => return None,
+    };
+
+    op.slope(value.data(), out.data())
+}
+
+#[must_use = "grad is re-enabled as soon as the guard is dropped"]
+pub struct NoGradGuard {
+    previous: bool,
+}
+
+impl Drop for NoGradGuard {
+    fn drop(&mut self) {
+        set_grad_enabled(self.previous);
+    }
 }
 
+/// Converts an `f64` constant into the scalar type of a graph.
+pub(crate) fn cast<T: Scalar>(x: f64) -> T {
+    T::from(x).expect("constant is representable in the scalar type")
+}
+
+/// How a node passes its gradient on to its children in `prev`.
 #[derive(Clone)]
-pub struct Value(Rc<RefCell<InnerValue>>);
+pub(crate) enum Op<T> {
+    Leaf,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Powi(i32),
+    Exp,
+    Recip,
+    Ln,
+    Sqrt,
+    Sin,
+    Cos,
+    Tanh,
+    Sigmoid,
+    Softplus,
+    Relu,
+    LeakyRelu(T),
+    Elu(T),
+    Abs,
+    Max,
+    Min,
+    Clamp(T, T),
+    /// Average of any number of children.
+    Mean,
+    /// Largest of any number of children.
+    MaxReduce,
+    /// A closure installed through [`Value::set_backward`].
+    Custom(Shared<dyn BackwardFn>),
+}
+
+impl<T: Scalar> Op<T> {
+    /// Accumulates into `prev` the gradients of a node holding `out` with
+    /// gradient `grad`. Children are read at call time, like the closures
+    /// this replaced, so updates made after the forward pass are seen.
+    fn backward(&self, out: T, grad: T, prev: &[Value<T>]) {
+        match self {
+            Op::Leaf | Op::Custom(_) => {}
+            Op::Add => prev.iter().for_each(|c| c.accumulate_grad(grad)),
+            Op::Mean => {
+                let n = cast::<T>(prev.len() as f64);
+                prev.iter().for_each(|c| c.accumulate_grad(grad / n));
+            }
+            Op::MaxReduce => {
+                // all of it goes to one winner, the first on ties
+                let winner = prev.iter().skip(1).fold(&prev[0], |best, c| {
+                    if c.data() > best.data() {
+                        c
+                    } else {
+                        best
+                    }
+                });
+                winner.accumulate_grad(grad);
+            }
+            Op::Sub => {
+                prev[0].accumulate_grad(grad);
+                prev[1].accumulate_grad(-grad);
+            }
+            Op::Mul => {
+                let (l, r) = (&prev[0], &prev[1]);
+                l.accumulate_grad(r.data() * grad);
+                r.accumulate_grad(l.data() * grad);
+            }
+            Op::Div => {
+                let (l, r) = (&prev[0], &prev[1]);
+                l.accumulate_grad(grad / r.data());
+                r.accumulate_grad(-l.data() / r.data().powi(2) * grad);
+            }
+            Op::Pow => {
+                let (s, p) = (&prev[0], &prev[1]);
+                s.accumulate_grad((p.data() * s.data().powf(p.data() - T::one())) * grad);
+
+                // the exponent gradient needs ln(base), which only exists for positive bases
+                if s.data() > T::zero() {
+                    p.accumulate_grad(s.data().powf(p.data()) * s.data().ln() * grad);
+                }
+            }
+            Op::Max | Op::Min => {
+                let (l, r) = (&prev[0], &prev[1]);
+                let self_wins = match self {
+                    Op::Max => l.data() >= r.data(),
+                    _ => l.data() <= r.data(),
+                };
+
+                if self_wins {
+                    l.accumulate_grad(grad);
+                } else {
+                    r.accumulate_grad(grad);
+                }
+            }
+            unary => {
+                let s = &prev[0];
+
+                if let Some(slope) = unary.slope(s.data(), out) {
+                    s.accumulate_grad(slope * grad);
+                }
+            }
+        }
+    }
+
+    /// Local derivative of a unary op with input `x` and output `out`, or
+    /// `None` where no gradient flows.
+    fn slope(&self, x: T, out: T) -> Option<T> {
+        let slope = match self {
+            Op::Powi(0) => T::zero(),
+            Op::Powi(n) => cast::<T>(*n as f64) * x.powi(n - 1),
+            Op::Exp => out,
+            Op::Recip => -T::one() / x.powi(2),
+            Op::Ln => T::one() / x,
+            Op::Sqrt => cast::<T>(0.5) / x.sqrt(),
+            Op::Sin => x.cos(),
+            Op::Cos => -x.sin(),
+            Op::Tanh => T::one() - out.powi(2),
+            Op::Sigmoid => out * (T::one() - out),
+            Op::Softplus => T::one() / (T::one() + (-x).exp()),
+            Op::Relu if out > T::zero() => T::one(),
+            Op::Relu => T::zero(),
+            Op::LeakyRelu(_) if x > T::zero() => T::one(),
+            Op::LeakyRelu(negative_slope) => *negative_slope,
+            Op::Elu(_) if x > T::zero() => T::one(),
+            Op::Elu(alpha) => *alpha * x.exp(),
+            Op::Abs if x > T::zero() => T::one(),
+            Op::Abs if x < T::zero() => -T::one(),
+            Op::Abs => T::zero(),
+            // gradient only passes while strictly inside the range
+            Op::Clamp(lo, hi) if *lo < x && x < *hi => T::one(),
+            _ => return None,
+        };
+
+        Some(slope)
+    }
+
+    /// Like [`Op::backward`], but builds each child's share of the gradient
+    /// as a new graph from `grad` and the nodes themselves, so it can be
+    /// differentiated again. `out` is the node this op belongs to.
+    fn backward_graph(
+        &self,
+        out: &Value<T>,
+        grad: &Value<T>,
+        prev: &[Value<T>],
+    ) -> Vec<(Value<T>, Value<T>)> {
+        let child = |i: usize, share: Value<T>| (prev[i].clone(), share);
+
+        match self {
+            Op::Leaf => vec![],
+            Op::Custom(_) => panic!(
+                "can't differentiate the custom backward of {:?} node",
+                out.op()
+            ),
+            Op::Add => (0..prev.len()).map(|i| child(i, grad.clone())).collect(),
+            Op::Sub => vec![child(0, grad.clone()), child(1, -grad)],
+            Op::Mul => vec![child(0, grad * &prev[1]), child(1, grad * &prev[0])],
+            Op::Div => {
+                let (l, r) = (&prev[0], &prev[1]);
+
+                vec![child(0, grad / r), child(1, &(&(-grad) * l) / &(r * r))]
+            }
+            Op::Pow => {
+                let (s, p) = (&prev[0], &prev[1]);
+                let mut shares = vec![child(0, &(grad * p) * &s.powv(&(p - T::one())))];
+
+                if s.data() > T::zero() {
+                    shares.push(child(1, &(grad * out) * &s.ln()));
+                }
+
+                shares
+            }
+            Op::Mean => {
+                let n = cast::<T>(prev.len() as f64);
+
+                (0..prev.len()).map(|i| child(i, grad / n)).collect()
+            }
+            Op::Max | Op::Min | Op::MaxReduce => {
+                // the same winner the scalar pass picks
+                let winner = match self {
+                    Op::Max => usize::from(prev[0].data() < prev[1].data()),
+                    Op::Min => usize::from(prev[0].data() > prev[1].data()),
+                    _ => (1..prev.len()).fold(0, |best, i| {
+                        if prev[i].data() > prev[best].data() {
+                            i
+                        } else {
+                            best
+                        }
+                    }),
+                };
+
+                vec![child(winner, grad.clone())]
+            }
+            unary => {
+                let x = &prev[0];
+
+                let share = match unary {
+                    Op::Powi(0) => grad * T::zero(),
+                    Op::Powi(n) => grad * &(&x.powi(n - 1) * cast::<T>(*n as f64)),
+                    Op::Exp => grad * out,
+                    Op::Recip => &(-grad) / &(x * x),
+                    Op::Ln => grad / x,
+                    Op::Sqrt => grad / &(out * cast::<T>(2.0)),
+                    Op::Sin => grad * &x.cos(),
+                    Op::Cos => grad * &(-&x.sin()),
+                    Op::Tanh => grad * &(&(-&(out * out)) + T::one()),
+                    Op::Sigmoid => grad * &(out * &(&(-out) + T::one())),
+                    Op::Softplus => grad * &x.sigmoid(),
+                    Op::Elu(alpha) if x.data() <= T::zero() => grad * &(&x.exp() * *alpha),
+                    // the rest are piecewise constant
+                    _ => match unary.slope(x.data(), out.data()) {
+                        Some(slope) => grad * slope,
+                        None => return vec![],
+                    },
+                };
+
+                vec![(x.clone(), share)]
+            }
+        }
+    }
+}
+
+struct InnerValue<T> {
+    data: T,
+    grad: T,
+    backward: Op<T>,
+    prev: Vec<Value<T>>,
+    op: String,
+    id: usize,
+    requires_grad: bool,
+    name: Option<String>,
+}
+
+impl<T> Drop for InnerValue<T> {
+    fn drop(&mut self) {
+        // unlink children iteratively, otherwise dropping a deep graph
+        // recurses once per node and can overflow the stack
+        self.backward = Op::Leaf;
+        let mut stack = std::mem::take(&mut self.prev);
+
+        while let Some(value) = stack.pop() {
+            if let Ok(lock) = Shared::try_unwrap(value.0) {
+                let mut inner = lock.into_inner();
+                inner.backward = Op::Leaf;
+                stack.append(&mut inner.prev);
+            }
+        }
+    }
+}
+
+/// A scalar node in the computation graph.
+///
+/// The float type defaults to `f32`. Note that in expression position a bare
+/// `Value::new(2.0)` is inferred like any other float literal, so it becomes
+/// an `f64` graph unless something pins it down; use [`Value32`] or a type
+/// annotation when that matters.
+pub struct Value<T = f32>(Shared<Lock<InnerValue<T>>>);
+
+pub type Value32 = Value<f32>;
+pub type Value64 = Value<f64>;
+
+impl<T> Clone for Value<T> {
+    fn clone(&self) -> Self {
+        Value(Shared::clone(&self.0))
+    }
+}
 
-impl Debug for Value {
+/// Wraps a [`Value`] so hashing and equality go by node identity rather than
+/// data, for keying nodes in a `HashMap` or `HashSet`. Clones of a node are
+/// equal, distinct nodes never are, even when they hold the same data.
+#[derive(Clone, Debug)]
+pub struct ByIdentity<T = f32>(pub Value<T>);
+
+impl<T: Scalar> PartialEq for ByIdentity<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id() == other.0.id()
+    }
+}
+
+impl<T: Scalar> Eq for ByIdentity<T> {}
+
+impl<T: Scalar> Hash for ByIdentity<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.id().hash(state);
+    }
+}
+
+/// A non-owning handle to a [`Value`].
+///
+/// Custom closures passed to [`Value::set_backward`] should reach the node
+/// they are installed on through a `WeakValue`, since capturing a strong
+/// handle to it makes the node keep itself alive and leaks the whole graph
+/// beneath it.
+pub struct WeakValue<T = f32>(WeakShared<Lock<InnerValue<T>>>);
+
+impl<T> Clone for WeakValue<T> {
+    fn clone(&self) -> Self {
+        WeakValue(WeakShared::clone(&self.0))
+    }
+}
+
+impl<T: Scalar> WeakValue<T> {
+    pub fn upgrade(&self) -> Option<Value<T>> {
+        self.0.upgrade().map(Value)
+    }
+
+    /// Data of the referenced node. Panics if the node has been dropped.
+    pub fn data(&self) -> T {
+        self.upgrade().expect("value has been dropped").data()
+    }
+
+    /// Gradient of the referenced node. Panics if the node has been dropped.
+    pub fn grad(&self) -> T {
+        self.upgrade().expect("value has been dropped").grad()
+    }
+}
+
+impl<T: Debug> Debug for Value<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = self.0.read();
+
         write!(
             f,
             "Value(data={:?}, grad={:?}, prev={:?}, op={:?})",
-            self.data(),
-            self.grad(),
-            self.prev(),
-            self.op()
+            inner.data, inner.grad, inner.prev, inner.op
         )
     }
 }
 
-impl Display for Value {
+impl<T: Scalar> Display for Value<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Value(data={})", self.data())
     }
 }
 
-impl PartialEq for Value {
+impl<T: Scalar> PartialEq for Value<T> {
     fn eq(&self, other: &Self) -> bool {
         self.data() == other.data()
     }
 }
 
-impl PartialEq<f32> for Value {
-    fn eq(&self, other: &f32) -> bool {
+impl<T: Scalar> PartialEq<T> for Value<T> {
+    fn eq(&self, other: &T) -> bool {
         self.data() == *other
     }
 }
 
-impl PartialEq<Value> for f32 {
-    fn eq(&self, other: &Value) -> bool {
-        *self == other.data()
+impl<T: Scalar> PartialOrd for Value<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.data().partial_cmp(&other.data())
+    }
+}
+
+impl<T: Scalar> PartialOrd<T> for Value<T> {
+    fn partial_cmp(&self, other: &T) -> Option<Ordering> {
+        self.data().partial_cmp(other)
+    }
+}
+
+impl<T: Scalar> From<T> for Value<T> {
+    fn from(data: T) -> Value<T> {
+        Value::new(data)
     }
 }
 
-impl Value {
-    pub fn new(data: f32) -> Value {
-        let grad: f32 = 0.0;
-        let backward = Box::new(|| {});
-        let prev = Vec::<Value>::new();
+impl<T: Scalar> Value<T> {
+    pub fn new(data: T) -> Value<T> {
+        let grad = T::zero();
+        let backward = Op::Leaf;
+        let prev = Vec::<Value<T>>::new();
         let op = String::new();
 
         let value = InnerValue {
@@ -67,17 +468,25 @@ impl Value {
             backward,
             prev,
             op,
+            id: NEXT_ID.fetch_add(1, AtomicOrdering::Relaxed),
+            requires_grad: true,
+            name: None,
         };
 
-        Value(Rc::new(RefCell::new(value)))
+        Value(Shared::new(Lock::new(value)))
+    }
+
+    pub fn from_slice(data: &[T]) -> Vec<Value<T>> {
+        data.iter().copied().map(Value::new).collect()
     }
 
-    pub fn with_op(data: f32, children: Option<Vec<Value>>, op: &str) -> Value {
-        let grad: f32 = 0.0;
-        let backward = Box::new(|| {});
-        let prev = match children {
-            Some(c) => c,
-            None => Vec::<Value>::new(),
+    pub fn with_op(data: T, children: Option<Vec<Value<T>>>, op: &str) -> Value<T> {
+        let grad = T::zero();
+        let backward = Op::Leaf;
+        let prev = if is_grad_enabled() {
+            children.unwrap_or_default()
+        } else {
+            vec![]
         };
         let op = String::from(op);
 
@@ -87,324 +496,874 @@ impl Value {
             backward,
             prev,
             op,
+            id: NEXT_ID.fetch_add(1, AtomicOrdering::Relaxed),
+            requires_grad: true,
+            name: None,
         };
 
-        Value(Rc::new(RefCell::new(value)))
+        Value(Shared::new(Lock::new(value)))
+    }
+
+    pub(crate) fn with_backward(
+        data: T,
+        children: Vec<Value<T>>,
+        label: &str,
+        op: Op<T>,
+    ) -> Value<T> {
+        let value = Value::with_op(data, Some(children), label);
+        if is_grad_enabled() {
+            value.0.write().backward = op;
+        }
+
+        value
     }
 
-    pub fn powf(&self, pow: f32) -> Value {
+    pub fn powf(&self, pow: T) -> Value<T> {
         self.powv(&Value::new(pow))
     }
 
-    pub fn powv(&self, pow: &Value) -> Value {
+    /// Alias of [`Value::powv`].
+    pub fn pow(&self, pow: &Value<T>) -> Value<T> {
+        self.powv(pow)
+    }
+
+    /// Integer power by repeated multiplication, which is cheaper than
+    /// [`Value::powf`] and exact for small exponents, in a single node.
+    pub fn powi(&self, n: i32) -> Value<T> {
+        let data = self.data().powi(n);
+        let children = vec![self.to_owned()];
+
+        Value::with_backward(data, children, "powi", Op::Powi(n))
+    }
+
+    pub fn powv(&self, pow: &Value<T>) -> Value<T> {
         let data = self.data().powf(pow.data());
         let children = vec![self.to_owned(), pow.to_owned()];
 
-        let value = Value::with_op(data, Some(children), "pow");
+        Value::with_backward(data, children, "pow", Op::Pow)
+    }
+
+    pub fn exp(&self) -> Value<T> {
+        let data = self.data().exp();
+        let children = vec![self.to_owned()];
 
-        let v = value.clone();
-        let s = self.clone();
-        let p = pow.clone();
-        value.set_backward(move || {
-            s.accumulate_grad((p.data() * s.data().powf(p.data() - 1.0)) * v.grad());
-        });
+        Value::with_backward(data, children, "exp", Op::Exp)
+    }
+
+    /// `exp` of the input clamped to `[-max_abs, max_abs]`, which stays
+    /// finite where [`Value::exp`] would overflow. `88.0` is the largest
+    /// whole bound that is safe for `f32`. Outside the range the gradient is
+    /// zero, as for [`Value::clamp`].
+    pub fn exp_clamped(&self, max_abs: T) -> Value<T> {
+        let value = self.clamp(-max_abs, max_abs).exp();
+        value.set_op("exp_clamped");
 
         value
     }
 
-    pub fn exp(&self) -> Value {
-        let data = self.data().exp();
+    pub fn recip(&self) -> Value<T> {
+        let data = self.data().recip();
         let children = vec![self.to_owned()];
 
-        let value = Value::with_op(data, Some(children), "exp");
+        Value::with_backward(data, children, "recip", Op::Recip)
+    }
 
-        let v = value.clone();
-        let s = self.clone();
-        value.set_backward(move || {
-            s.accumulate_grad(v.data() * v.grad());
-        });
+    /// Natural logarithm. Follows `f32::ln` for inputs outside the domain:
+    /// `0.0` gives `-inf` and negative inputs give `NaN`, and both carry
+    /// through any gradient computed from this node.
+    pub fn ln(&self) -> Value<T> {
+        let data = self.data().ln();
+        let children = vec![self.to_owned()];
 
-        value
+        Value::with_backward(data, children, "ln", Op::Ln)
     }
 
-    pub fn tanh(&self) -> Value {
-        // just flexing
-        let ref num = &(2.0 * self).exp() - 1.0;
-        let ref den = &(2.0 * self).exp() + 1.0;
-        let value = num / den;
-        value.set_op("tanh");
+    /// Square root. The gradient `0.5 / sqrt(x)` is infinite at `0.0`, so
+    /// anything flowing back through a zero input becomes `inf` (or `NaN`
+    /// when the upstream gradient is also zero).
+    pub fn sqrt(&self) -> Value<T> {
+        let data = self.data().sqrt();
+        let children = vec![self.to_owned()];
 
-        let v = value.clone();
-        let s = self.clone();
-        value.set_backward(move || {
-            s.accumulate_grad((1.0 - v.data().powi(2)) * v.grad());
-        });
+        Value::with_backward(data, children, "sqrt", Op::Sqrt)
+    }
 
-        value
+    pub fn sin(&self) -> Value<T> {
+        let data = self.data().sin();
+        let children = vec![self.to_owned()];
+
+        Value::with_backward(data, children, "sin", Op::Sin)
     }
 
-    pub fn is_in(&self, values: &Vec<Value>) -> bool {
-        for value in values {
-            if Rc::ptr_eq(&self.0, &value.0) {
-                return true;
-            }
-        }
+    pub fn cos(&self) -> Value<T> {
+        let data = self.data().cos();
+        let children = vec![self.to_owned()];
 
-        false
+        Value::with_backward(data, children, "cos", Op::Cos)
     }
 
-    pub fn build_topo(&self, visited: &mut Vec<Value>, topo: &mut Vec<Value>) -> Vec<Value> {
-        if !self.is_in(&visited) {
-            visited.push(self.clone());
+    pub fn tanh(&self) -> Value<T> {
+        let data = self.data().tanh();
+        let children = vec![self.to_owned()];
 
-            for child in self.prev() {
-                *topo = child.build_topo(visited, topo);
-            }
+        Value::with_backward(data, children, "tanh", Op::Tanh)
+    }
 
-            topo.push(self.clone());
-        }
+    pub fn sigmoid(&self) -> Value<T> {
+        let data = T::one() / (T::one() + (-self.data()).exp());
+        let children = vec![self.to_owned()];
 
-        topo.clone()
+        Value::with_backward(data, children, "sigmoid", Op::Sigmoid)
     }
 
-    pub fn backward(&self) {
-        let mut topo = self.build_topo(&mut Vec::<Value>::new(), &mut Vec::<Value>::new());
-        topo.reverse();
+    /// `ln(1 + exp(x))`, evaluated as `max(x, 0) + ln(1 + exp(-|x|))` so
+    /// large inputs don't overflow the exponential.
+    pub fn softplus(&self) -> Value<T> {
+        let x = self.data();
+        let data = x.max(T::zero()) + (-x.abs()).exp().ln_1p();
+        let children = vec![self.to_owned()];
 
-        self.set_grad(1.0);
-        topo.iter().for_each(|value| value.once_backward());
+        Value::with_backward(data, children, "softplus", Op::Softplus)
     }
 
-    pub fn once_backward(&self) {
-        (self.0.borrow().backward)();
+    pub fn relu(&self) -> Value<T> {
+        let data = self.data().max(T::zero());
+        let children = vec![self.to_owned()];
+
+        Value::with_backward(data, children, "relu", Op::Relu)
     }
 
-    pub fn set_backward<F: Fn() + 'static>(&self, backward: F) {
-        self.0.borrow_mut().backward = Box::new(backward);
+    pub fn leaky_relu(&self, negative_slope: T) -> Value<T> {
+        let data = if self.data() > T::zero() {
+            self.data()
+        } else {
+            negative_slope * self.data()
+        };
+        let children = vec![self.to_owned()];
+
+        Value::with_backward(data, children, "leaky_relu", Op::LeakyRelu(negative_slope))
     }
 
-    pub fn data(&self) -> f32 {
-        self.0.borrow().data.clone()
+    pub fn elu(&self, alpha: T) -> Value<T> {
+        let data = if self.data() > T::zero() {
+            self.data()
+        } else {
+            alpha * (self.data().exp() - T::one())
+        };
+        let children = vec![self.to_owned()];
+
+        Value::with_backward(data, children, "elu", Op::Elu(alpha))
     }
 
-    pub fn set_data(&self, data: f32) {
-        self.0.borrow_mut().data = data;
+    /// GELU via the tanh approximation
+    /// `0.5 * x * (1 + tanh(sqrt(2 / pi) * (x + 0.044715 * x^3)))`.
+    ///
+    /// Composed from existing ops, so the gradient flows through the
+    /// intermediate nodes; only the output is relabeled.
+    pub fn gelu(&self) -> Value<T> {
+        let c = cast::<T>((2.0 / std::f64::consts::PI).sqrt());
+        let inner = &(self + &(&self.powf(cast(3.0)) * cast::<T>(0.044715))) * c;
+
+        let value = &(self * cast::<T>(0.5)) * &(&inner.tanh() + T::one());
+        value.set_op("gelu");
+
+        value
     }
 
-    pub fn grad(&self) -> f32 {
-        self.0.borrow().grad.clone()
+    /// Absolute value. The subgradient at `0.0` is taken to be `0.0`.
+    pub fn abs(&self) -> Value<T> {
+        let data = self.data().abs();
+        let children = vec![self.to_owned()];
+
+        Value::with_backward(data, children, "abs", Op::Abs)
     }
 
-    pub fn set_grad(&self, grad: f32) {
-        self.0.borrow_mut().grad = grad;
+    /// Larger of two values. The gradient goes to whichever operand won,
+    /// with ties going to `self`.
+    pub fn max(&self, other: &Value<T>) -> Value<T> {
+        let data = self.data().max(other.data());
+        let children = vec![self.to_owned(), other.to_owned()];
+
+        Value::with_backward(data, children, "max", Op::Max)
     }
 
-    pub fn accumulate_grad(&self, grad: f32) {
-        self.0.borrow_mut().grad += grad;
+    /// Smaller of two values. The gradient goes to whichever operand won,
+    /// with ties going to `self`.
+    pub fn min(&self, other: &Value<T>) -> Value<T> {
+        let data = self.data().min(other.data());
+        let children = vec![self.to_owned(), other.to_owned()];
+
+        Value::with_backward(data, children, "min", Op::Min)
     }
 
-    pub fn prev(&self) -> Vec<Value> {
-        self.0.borrow().prev.clone()
+    /// Clips the value into `[lo, hi]`. Gradient only passes through while
+    /// the input is strictly inside the range.
+    pub fn clamp(&self, lo: T, hi: T) -> Value<T> {
+        let data = self.data().max(lo).min(hi);
+        let children = vec![self.to_owned()];
+
+        Value::with_backward(data, children, "clamp", Op::Clamp(lo, hi))
     }
 
-    pub fn set_prev(&self, prev: Vec<Value>) {
-        self.0.borrow_mut().prev = prev;
+    /// A new leaf holding the same data, cut off from this node's graph.
+    /// Gradient flowing into the result never reaches `self`.
+    pub fn detach(&self) -> Value<T> {
+        Value::new(self.data())
     }
 
-    pub fn op(&self) -> String {
-        self.0.borrow().op.clone()
+    #[allow(clippy::ptr_arg)]
+    pub fn is_in(&self, values: &Vec<Value<T>>) -> bool {
+        for value in values {
+            if Shared::ptr_eq(&self.0, &value.0) {
+                return true;
+            }
+        }
+
+        false
     }
 
-    pub fn set_op(&self, op: &str) {
-        self.0.borrow_mut().op = String::from(op);
+    /// Appends to `topo`, children first, every node reachable from `self`
+    /// that isn't already in `visited`, marking them visited, and returns a
+    /// copy of `topo`.
+    pub fn build_topo(
+        &self,
+        visited: &mut Vec<Value<T>>,
+        topo: &mut Vec<Value<T>>,
+    ) -> Vec<Value<T>> {
+        let mut seen: HashSet<usize> = visited.iter().map(|v| v.id()).collect();
+        let start = topo.len();
+
+        Value::build_topo_into(std::slice::from_ref(self), &mut seen, topo);
+        visited.extend_from_slice(&topo[start..]);
+
+        topo.clone()
     }
-}
 
-impl Sum for Value {
-    fn sum<I: Iterator<Item = Value>>(iter: I) -> Value {
-        iter.fold(Value::new(0.0), ops::Add::add)
+    /// Topological order of everything reachable from `self`.
+    pub(crate) fn topo(&self) -> Vec<Value<T>> {
+        Value::build_topo_from(std::slice::from_ref(self))
     }
-}
 
-impl ops::Add for Value {
-    type Output = Value;
+    /// Topological order of everything reachable from any of `roots`, each
+    /// node once.
+    fn build_topo_from(roots: &[Value<T>]) -> Vec<Value<T>> {
+        let mut topo = Vec::new();
+        Value::build_topo_into(roots, &mut HashSet::new(), &mut topo);
 
-    fn add(self, rhs: Value) -> Value {
-        &self + &rhs
+        topo
     }
-}
 
-impl ops::Add for &Value {
-    type Output = Value;
+    fn build_topo_into(roots: &[Value<T>], visited: &mut HashSet<usize>, topo: &mut Vec<Value<T>>) {
+        // explicit post-order dfs, children are pushed in reverse so they
+        // are emitted in the same order a recursive walk would emit them
+        let mut stack: Vec<(Value<T>, bool)> = roots
+            .iter()
+            .rev()
+            .map(|root| (root.clone(), false))
+            .collect();
+
+        while let Some((value, expanded)) = stack.pop() {
+            if expanded {
+                topo.push(value);
+            } else if visited.insert(value.id()) {
+                let children = value.prev();
+
+                stack.push((value, true));
+                stack.extend(children.into_iter().rev().map(|child| (child, false)));
+            }
+        }
+    }
 
-    fn add(self, rhs: &Value) -> Value {
-        let result = self.data() + rhs.data();
-        let children = vec![self.to_owned(), rhs.to_owned()];
+    /// Looks for a NaN or infinite `data` or `grad` anywhere in the graph.
+    /// Children are checked before their parents, so the error names the
+    /// node where things first went wrong rather than everything downstream.
+    pub fn check_finite(&self) -> Result<(), NonFiniteError> {
+        match self
+            .topo()
+            .into_iter()
+            .find(|v| !v.data().is_finite() || !v.grad().is_finite())
+        {
+            Some(v) => Err(NonFiniteError {
+                id: v.id(),
+                op: v.op(),
+                data: v.data().to_f64().unwrap_or(f64::NAN),
+                grad: v.grad().to_f64().unwrap_or(f64::NAN),
+            }),
+            None => Ok(()),
+        }
+    }
 
-        let value = Value::with_op(result, Some(children), "+");
+    /// Number of distinct nodes reachable from this one, itself included.
+    pub fn graph_size(&self) -> usize {
+        self.topo().len()
+    }
 
-        let v = value.clone();
-        let l = self.clone();
-        let r = rhs.clone();
-        value.set_backward(move || {
-            l.accumulate_grad(1.0 * v.grad());
-            r.accumulate_grad(1.0 * v.grad());
-        });
+    /// Length of the longest path from this node down to a leaf, so a leaf
+    /// has depth 0.
+    pub fn graph_depth(&self) -> usize {
+        // topo order has every child before its parents
+        let mut depths: HashMap<usize, usize> = HashMap::new();
+
+        for value in self.topo() {
+            let depth = value
+                .prev()
+                .iter()
+                .map(|child| depths[&child.id()] + 1)
+                .max()
+                .unwrap_or(0);
+
+            depths.insert(value.id(), depth);
+        }
 
-        value
+        depths[&self.id()]
     }
-}
 
-impl ops::Add<&Value> for f32 {
-    type Output = Value;
+    /// Renders the graph ending at this node as a Graphviz `digraph`.
+    ///
+    /// Values are drawn as record nodes showing their data and grad, and every
+    /// node produced by an op gets a small circle node for the op feeding it.
+    pub fn to_dot(&self) -> String {
+        let topo = self.topo();
+        let ids: HashMap<usize, usize> = topo
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (value.id(), i))
+            .collect();
+
+        let mut dot = String::from("digraph {\n    rankdir=LR;\n");
+
+        for (i, value) in topo.iter().enumerate() {
+            dot.push_str(&format!(
+                "    n{} [label=\"{{ data {:.4} | grad {:.4} }}\", shape=record];\n",
+                i,
+                value.data(),
+                value.grad()
+            ));
+
+            let op = value.op();
+            if !op.is_empty() {
+                dot.push_str(&format!(
+                    "    n{}_op [label=\"{}\", shape=circle];\n",
+                    i, op
+                ));
+                dot.push_str(&format!("    n{}_op -> n{};\n", i, i));
+            }
+
+            for child in value.prev() {
+                let target = if op.is_empty() {
+                    format!("n{}", i)
+                } else {
+                    format!("n{}_op", i)
+                };
 
-    fn add(self, rhs: &Value) -> Value {
-        let lhs = &Value::new(self);
+                dot.push_str(&format!("    n{} -> {};\n", ids[&child.id()], target));
+            }
+        }
 
-        lhs + rhs
+        dot.push('}');
+
+        dot
     }
-}
 
-impl ops::Add<f32> for &Value {
-    type Output = Value;
+    pub fn backward(&self) {
+        let mut topo = self.topo();
+        topo.reverse();
 
-    fn add(self, rhs: f32) -> Value {
-        let rhs = &Value::new(rhs);
+        self.set_grad(T::one());
+        topo.iter().for_each(|value| value.once_backward());
+    }
 
-        self + rhs
+    /// Backpropagates from several outputs at once, seeding each with its
+    /// upstream gradient, e.g. one loss per task of a multi-task net. Shared
+    /// parts of the graph are visited once, so the result is the same as
+    /// building and backpropagating each output on its own.
+    pub fn backward_from(grads: &[(Value<T>, T)]) {
+        let roots: Vec<Value<T>> = grads.iter().map(|(value, _)| value.clone()).collect();
+        let mut topo = Value::build_topo_from(&roots);
+        topo.reverse();
+
+        roots.iter().for_each(|root| root.zero_grad());
+        grads
+            .iter()
+            .for_each(|(value, grad)| value.accumulate_grad(*grad));
+        topo.iter().for_each(|value| value.once_backward());
     }
-}
 
-impl ops::Sub for &Value {
-    type Output = Value;
+    /// Gradients of this node with respect to each of `wrt`, as graphs of
+    /// their own rather than numbers, like `torch.autograd.grad` with
+    /// `create_graph=True`. Differentiating a result again gives second
+    /// derivatives, e.g. Hessian-vector products.
+    ///
+    /// Plain [`Value::backward`] keeps `grad` a scalar so ordinary training
+    /// doesn't pay for an extra graph; this pass instead builds one node per
+    /// gradient term out of the forward nodes. It leaves every `grad` alone,
+    /// and panics before doing any work if the graph has a node with a custom
+    /// backward closure, e.g. from [`Value::set_backward`], since those only
+    /// produce numbers. Anything `wrt` that this node doesn't depend on gets
+    /// a constant zero.
+    pub fn gradients(&self, wrt: &[Value<T>]) -> Vec<Value<T>> {
+        let mut topo = self.topo();
+        topo.reverse();
+
+        if let Some(custom) = topo
+            .iter()
+            .find(|v| matches!(v.0.read().backward, Op::Custom(_)))
+        {
+            panic!(
+                "gradients can't differentiate through the {:?} node, its custom backward only supports backward()",
+                custom.op()
+            );
+        }
 
-    fn sub(self, rhs: &Value) -> Value {
-        let value = self + &(-rhs);
-        value.set_op("-");
+        let mut adjoints: HashMap<usize, Value<T>> = HashMap::new();
+        adjoints.insert(self.id(), Value::new(T::one()));
+
+        topo.iter().for_each(|value| {
+            let Some(grad) = adjoints.get(&value.id()).cloned() else {
+                return;
+            };
+
+            let (op, prev) = {
+                let inner = value.0.read();
+                (inner.backward.clone(), inner.prev.clone())
+            };
+
+            op.backward_graph(value, &grad, &prev)
+                .into_iter()
+                .for_each(|(child, share)| {
+                    let total = match adjoints.remove(&child.id()) {
+                        Some(acc) => &acc + &share,
+                        None => share,
+                    };
+                    adjoints.insert(child.id(), total);
+                });
+        });
 
-        value
+        wrt.iter()
+            .map(|w| {
+                adjoints
+                    .get(&w.id())
+                    .cloned()
+                    .unwrap_or_else(|| Value::new(T::zero()))
+            })
+            .collect()
     }
-}
 
-impl ops::Sub<&Value> for f32 {
-    type Output = Value;
+    /// Runs [`Value::backward`] and returns the grad of every node in the
+    /// graph that was given a name with [`Value::set_name`], to inspect
+    /// intermediate results. If a name repeats, the node nearest this one
+    /// wins.
+    pub fn backward_with_names(&self) -> HashMap<String, T> {
+        let mut topo = self.topo();
+        topo.reverse();
 
-    fn sub(self, rhs: &Value) -> Value {
-        let value = self + &(-rhs);
-        value.set_op("-");
+        self.set_grad(T::one());
+        topo.iter().for_each(|value| value.once_backward());
 
-        value
+        let mut grads = HashMap::new();
+        topo.iter().for_each(|value| {
+            if let Some(name) = value.name() {
+                grads.entry(name).or_insert_with(|| value.grad());
+            }
+        });
+
+        grads
     }
-}
 
-impl ops::Sub<f32> for &Value {
-    type Output = Value;
+    pub fn once_backward(&self) {
+        let inner = self.0.read();
+
+        if let Op::Custom(backward) = &inner.backward {
+            // run the closure without holding the lock, it reads this node's grad
+            let backward = Shared::clone(backward);
+            drop(inner);
 
-    fn sub(self, rhs: f32) -> Value {
-        let value = self + (-rhs);
-        value.set_op("-");
+            return backward();
+        }
 
-        value
+        inner.backward.backward(inner.data, inner.grad, &inner.prev);
     }
-}
 
-impl ops::Mul for &Value {
-    type Output = Value;
+    /// Installs a custom backward pass. Ignored while grad is disabled, see
+    /// [`set_grad_enabled`].
+    pub fn set_backward<F: BackwardFn>(&self, backward: F) {
+        if is_grad_enabled() {
+            self.0.write().backward = Op::Custom(Shared::new(backward));
+        }
+    }
 
-    fn mul(self, rhs: &Value) -> Value {
-        let result = self.data() * rhs.data();
-        let children = vec![self.to_owned(), rhs.to_owned()];
+    /// Creates a [`WeakValue`] pointing at this node.
+    ///
+    /// Every handle a caller holds stays valid: a graph is freed once the
+    /// last strong handle to its output is dropped, while leaves and
+    /// parameters held elsewhere (e.g. by a [`crate::neuron::Neuron`]) live on.
+    pub fn downgrade(&self) -> WeakValue<T> {
+        WeakValue(Shared::downgrade(&self.0))
+    }
 
-        let value = Value::with_op(result, Some(children), "*");
+    /// Identifier assigned when the node was created. Clones share it,
+    /// distinct nodes never do.
+    pub fn id(&self) -> usize {
+        self.0.read().id
+    }
 
-        let v = value.clone();
-        let l = self.clone();
-        let r = rhs.clone();
-        value.set_backward(Box::new(move || {
-            l.accumulate_grad(r.data() * v.grad());
-            r.accumulate_grad(l.data() * v.grad());
-        }));
+    pub fn data(&self) -> T {
+        self.0.read().data
+    }
 
-        value
+    pub fn set_data(&self, data: T) {
+        self.0.write().data = data;
     }
-}
 
-impl ops::Mul<f32> for &Value {
-    type Output = Value;
+    pub fn grad(&self) -> T {
+        self.0.read().grad
+    }
 
-    fn mul(self, rhs: f32) -> Value {
-        let rhs = &Value::new(rhs);
+    pub fn set_grad(&self, grad: T) {
+        self.0.write().grad = grad;
+    }
 
-        self * rhs
+    pub fn zero_grad(&self) {
+        self.set_grad(T::zero());
     }
-}
 
-impl ops::Mul<&Value> for f32 {
-    type Output = Value;
+    /// Adds `delta` to the data in place.
+    pub fn apply_update(&self, delta: T) {
+        self.set_data(self.data() + delta);
+    }
 
-    fn mul(self, rhs: &Value) -> Value {
-        let lhs = &Value::new(self);
+    /// Plain gradient descent step, moving the data against its gradient by
+    /// `lr * grad`.
+    pub fn sgd_step(&self, lr: T) {
+        self.apply_update(-lr * self.grad());
+    }
 
-        lhs * rhs
+    /// Whether optimizers should update this node, true unless frozen with
+    /// [`Value::set_requires_grad`].
+    pub fn requires_grad(&self) -> bool {
+        self.0.read().requires_grad
     }
-}
 
-impl ops::Div for &Value {
-    type Output = Value;
+    /// Freezes (`false`) or unfreezes a parameter. Backward still fills in
+    /// the grad of a frozen node, since gradients have to flow through it to
+    /// anything below; only optimizer steps leave it alone.
+    pub fn set_requires_grad(&self, requires_grad: bool) {
+        self.0.write().requires_grad = requires_grad;
+    }
 
-    fn div(self, rhs: &Value) -> Value {
-        let value = self * &rhs.powf(-1.0);
-        value.set_op("/");
+    pub fn accumulate_grad(&self, grad: T) {
+        let mut inner = self.0.write();
+        inner.grad = inner.grad + grad;
+    }
 
-        value
+    pub fn prev(&self) -> Vec<Value<T>> {
+        self.0.read().prev.clone()
     }
-}
 
-impl ops::Div<f32> for &Value {
-    type Output = Value;
+    pub fn set_prev(&self, prev: Vec<Value<T>>) {
+        self.0.write().prev = prev;
+    }
 
-    fn div(self, rhs: f32) -> Value {
-        let value = self * rhs.powf(-1.0);
-        value.set_op("/");
+    pub fn name(&self) -> Option<String> {
+        self.0.read().name.clone()
+    }
 
-        value
+    /// Tags the node for [`Value::backward_with_names`].
+    pub fn set_name(&self, name: &str) {
+        self.0.write().name = Some(String::from(name));
     }
-}
 
-impl ops::Div<&Value> for f32 {
-    type Output = Value;
+    pub fn op(&self) -> String {
+        self.0.read().op.clone()
+    }
 
-    fn div(self, rhs: &Value) -> Value {
-        let value = self * &rhs.powf(-1.0);
-        value.set_op("/");
+    pub fn set_op(&self, op: &str) {
+        self.0.write().op = String::from(op);
+    }
+}
 
-        value
+impl<T: Scalar> Sum for Value<T> {
+    fn sum<I: Iterator<Item = Value<T>>>(iter: I) -> Value<T> {
+        iter.fold(Value::new(T::zero()), ops::Add::add)
     }
 }
 
-impl ops::Neg for Value {
-    type Output = Value;
+impl<T: Scalar> ops::Add for Value<T> {
+    type Output = Value<T>;
 
-    fn neg(self) -> Value {
-        &self * -1.0
+    fn add(self, rhs: Value<T>) -> Value<T> {
+        &self + &rhs
     }
 }
 
-impl ops::Neg for &Value {
-    type Output = Value;
+impl<T: Scalar> ops::Add for &Value<T> {
+    type Output = Value<T>;
 
-    fn neg(self) -> Value {
-        self * -1.0
+    fn add(self, rhs: &Value<T>) -> Value<T> {
+        let result = self.data() + rhs.data();
+        let children = vec![self.to_owned(), rhs.to_owned()];
+
+        Value::with_backward(result, children, "+", Op::Add)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::Value;
+impl<T: Scalar> ops::Add<T> for &Value<T> {
+    type Output = Value<T>;
 
-    #[test]
-    fn test_value() {
-        let v = Value::new(5.0);
+    fn add(self, rhs: T) -> Value<T> {
+        let rhs = &Value::new(rhs);
+
+        self + rhs
+    }
+}
+
+impl<T: Scalar> ops::AddAssign<&Value<T>> for Value<T> {
+    fn add_assign(&mut self, rhs: &Value<T>) {
+        *self = &*self + rhs;
+    }
+}
+
+impl<T: Scalar> ops::AddAssign<T> for Value<T> {
+    fn add_assign(&mut self, rhs: T) {
+        *self = &*self + rhs;
+    }
+}
+
+impl<T: Scalar> ops::Sub for Value<T> {
+    type Output = Value<T>;
+
+    fn sub(self, rhs: Value<T>) -> Value<T> {
+        &self - &rhs
+    }
+}
+
+impl<T: Scalar> ops::Sub for &Value<T> {
+    type Output = Value<T>;
+
+    fn sub(self, rhs: &Value<T>) -> Value<T> {
+        let result = self.data() - rhs.data();
+        let children = vec![self.to_owned(), rhs.to_owned()];
+
+        Value::with_backward(result, children, "-", Op::Sub)
+    }
+}
+
+impl<T: Scalar> ops::Sub<T> for &Value<T> {
+    type Output = Value<T>;
+
+    fn sub(self, rhs: T) -> Value<T> {
+        let rhs = &Value::new(rhs);
+
+        self - rhs
+    }
+}
+
+impl<T: Scalar> ops::Mul for Value<T> {
+    type Output = Value<T>;
+
+    fn mul(self, rhs: Value<T>) -> Value<T> {
+        &self * &rhs
+    }
+}
+
+impl<T: Scalar> ops::Mul for &Value<T> {
+    type Output = Value<T>;
+
+    fn mul(self, rhs: &Value<T>) -> Value<T> {
+        let result = self.data() * rhs.data();
+        let children = vec![self.to_owned(), rhs.to_owned()];
+
+        Value::with_backward(result, children, "*", Op::Mul)
+    }
+}
+
+impl<T: Scalar> ops::Mul<T> for &Value<T> {
+    type Output = Value<T>;
+
+    fn mul(self, rhs: T) -> Value<T> {
+        let rhs = &Value::new(rhs);
+
+        self * rhs
+    }
+}
+
+impl<T: Scalar> ops::MulAssign<&Value<T>> for Value<T> {
+    fn mul_assign(&mut self, rhs: &Value<T>) {
+        *self = &*self * rhs;
+    }
+}
+
+impl<T: Scalar> ops::MulAssign<T> for Value<T> {
+    fn mul_assign(&mut self, rhs: T) {
+        *self = &*self * rhs;
+    }
+}
+
+impl<T: Scalar> ops::Div for Value<T> {
+    type Output = Value<T>;
+
+    fn div(self, rhs: Value<T>) -> Value<T> {
+        &self / &rhs
+    }
+}
+
+impl<T: Scalar> ops::Div for &Value<T> {
+    type Output = Value<T>;
+
+    fn div(self, rhs: &Value<T>) -> Value<T> {
+        let result = self.data() / rhs.data();
+        let children = vec![self.to_owned(), rhs.to_owned()];
+
+        Value::with_backward(result, children, "/", Op::Div)
+    }
+}
+
+impl<T: Scalar> ops::Div<T> for &Value<T> {
+    type Output = Value<T>;
+
+    fn div(self, rhs: T) -> Value<T> {
+        let rhs = &Value::new(rhs);
+
+        self / rhs
+    }
+}
+
+impl<T: Scalar> ops::Neg for Value<T> {
+    type Output = Value<T>;
+
+    fn neg(self) -> Value<T> {
+        &self * -T::one()
+    }
+}
+
+impl<T: Scalar> ops::Neg for &Value<T> {
+    type Output = Value<T>;
+
+    fn neg(self) -> Value<T> {
+        self * -T::one()
+    }
+}
+
+// impls with a primitive float on the left can't be written generically,
+// so they are stamped out for each supported scalar type
+macro_rules! impl_scalar_lhs {
+    ($($t:ty),*) => {$(
+        impl From<i32> for Value<$t> {
+            fn from(data: i32) -> Value<$t> {
+                Value::new(data as $t)
+            }
+        }
+
+        impl PartialEq<Value<$t>> for $t {
+            fn eq(&self, other: &Value<$t>) -> bool {
+                *self == other.data()
+            }
+        }
+
+        impl PartialOrd<Value<$t>> for $t {
+            fn partial_cmp(&self, other: &Value<$t>) -> Option<Ordering> {
+                self.partial_cmp(&other.data())
+            }
+        }
+
+        impl ops::Add<&Value<$t>> for $t {
+            type Output = Value<$t>;
+
+            fn add(self, rhs: &Value<$t>) -> Value<$t> {
+                let lhs = &Value::new(self);
+
+                lhs + rhs
+            }
+        }
+
+        impl ops::Sub<&Value<$t>> for $t {
+            type Output = Value<$t>;
+
+            fn sub(self, rhs: &Value<$t>) -> Value<$t> {
+                let lhs = &Value::new(self);
+
+                lhs - rhs
+            }
+        }
+
+        impl ops::Mul<&Value<$t>> for $t {
+            type Output = Value<$t>;
+
+            fn mul(self, rhs: &Value<$t>) -> Value<$t> {
+                let lhs = &Value::new(self);
+
+                lhs * rhs
+            }
+        }
+
+        impl ops::Div<&Value<$t>> for $t {
+            type Output = Value<$t>;
+
+            fn div(self, rhs: &Value<$t>) -> Value<$t> {
+                let lhs = &Value::new(self);
+
+                lhs / rhs
+            }
+        }
+
+        impl ops::Add<Value<$t>> for $t {
+            type Output = Value<$t>;
+
+            fn add(self, rhs: Value<$t>) -> Value<$t> {
+                self + &rhs
+            }
+        }
+
+        impl ops::Sub<Value<$t>> for $t {
+            type Output = Value<$t>;
+
+            fn sub(self, rhs: Value<$t>) -> Value<$t> {
+                self - &rhs
+            }
+        }
+
+        impl ops::Mul<Value<$t>> for $t {
+            type Output = Value<$t>;
+
+            fn mul(self, rhs: Value<$t>) -> Value<$t> {
+                self * &rhs
+            }
+        }
+
+        impl ops::Div<Value<$t>> for $t {
+            type Output = Value<$t>;
+
+            fn div(self, rhs: Value<$t>) -> Value<$t> {
+                self / &rhs
+            }
+        }
+    )*};
+}
+
+impl_scalar_lhs!(f32, f64);
+
+// test_value_backward and test_value_grad_accumulates predate running clippy
+// on the tests and are kept as originally written
+#[cfg(test)]
+#[allow(
+    clippy::approx_constant,
+    clippy::excessive_precision,
+    clippy::toplevel_ref_arg
+)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use crate::shared::Shared;
+
+    use super::{
+        activation_derivative, is_grad_enabled, no_grad, set_grad_enabled, ByIdentity, Value,
+        Value32, Value64,
+    };
+
+    #[test]
+    fn test_value() {
+        let v = Value::new(5.0);
         let f: f32 = 5.0;
 
         assert_eq!(
@@ -451,6 +1410,270 @@ mod tests {
         assert_eq!(b.grad(), 5.0);
     }
 
+    #[test]
+    fn test_value_sub_backward() {
+        let a = &Value::new(5.0);
+        let b = &Value::new(2.0);
+
+        let composed = &(a + &(-b)) * a;
+        composed.backward();
+        let composed_grads = (a.grad(), b.grad());
+
+        a.zero_grad();
+        b.zero_grad();
+        let direct = &(a - b) * a;
+        direct.backward();
+
+        assert_eq!(direct.data(), composed.data());
+        assert_eq!((a.grad(), b.grad()), composed_grads);
+        assert_eq!((a.grad(), b.grad()), (8.0, -5.0));
+
+        // a single "-" node instead of + over a negation and its -1 constant
+        let diff = a - b;
+        assert_eq!(diff.op(), String::from("-"));
+        assert_eq!(diff.graph_size(), 3);
+        assert_eq!((a + &(-b)).graph_size(), 5);
+    }
+
+    #[test]
+    fn test_value_div_backward() {
+        let a = &Value::new(3.0_f64);
+        let b = &Value::new(4.0);
+
+        let composed = &(a * &b.powf(-1.0)) * a;
+        composed.backward();
+        let composed_grads = (a.grad(), b.grad());
+
+        a.zero_grad();
+        b.zero_grad();
+        let direct = &(a / b) * a;
+        direct.backward();
+
+        assert_eq!(direct.data(), composed.data());
+        assert!((a.grad() - composed_grads.0).abs() < 1e-12);
+        assert!((b.grad() - composed_grads.1).abs() < 1e-12);
+        // d/da a^2 / b = 2a / b, d/db = -a^2 / b^2
+        assert_eq!((a.grad(), b.grad()), (1.5, -9.0 / 16.0));
+
+        let quotient = a / b;
+        assert_eq!(quotient.op(), String::from("/"));
+        assert_eq!(quotient.graph_depth(), 1);
+        assert_eq!((a * &b.powf(-1.0)).graph_depth(), 2);
+    }
+
+    #[test]
+    fn test_value_add_assign() {
+        let xs = [Value::new(1.0_f32), Value::new(2.0), Value::new(3.0)];
+
+        let mut loss = Value::new(0.0);
+        for x in xs.iter() {
+            loss += &(x * x);
+        }
+        loss += 1.0;
+        loss.backward();
+
+        assert_eq!(loss.data(), 15.0);
+        assert_eq!(loss.op(), String::from("+"));
+        xs.iter().for_each(|x| assert_eq!(x.grad(), 2.0 * x.data()));
+
+        // same graph shape as chaining + by hand
+        let expected = &(&(&(&Value::new(0.0) + &(&xs[0] * &xs[0])) + &(&xs[1] * &xs[1]))
+            + &(&xs[2] * &xs[2]))
+            + 1.0;
+        assert_eq!(loss.topo().len(), expected.topo().len());
+    }
+
+    #[test]
+    fn test_value_mul_assign() {
+        let a = &Value::new(3.0_f32);
+
+        let mut out = Value::new(1.0);
+        out *= a;
+        out *= a;
+        out *= 2.0;
+        out.backward();
+
+        assert_eq!(out.data(), 18.0);
+        assert_eq!(a.grad(), 12.0);
+    }
+
+    #[test]
+    fn test_value_owned_ops_match_ref_ops() {
+        let check = |owned: fn(Value32, Value32) -> Value32,
+                     by_ref: fn(&Value32, &Value32) -> Value32| {
+            let (a1, b1) = (Value::new(3.0_f32), Value::new(-2.0_f32));
+            let (a2, b2) = (Value::new(3.0_f32), Value::new(-2.0_f32));
+
+            let c1 = owned(a1.clone(), b1.clone());
+            let c2 = by_ref(&a2, &b2);
+            c1.backward();
+            c2.backward();
+
+            assert_eq!(c1.data(), c2.data());
+            assert_eq!(c1.op(), c2.op());
+            assert_eq!(a1.grad(), a2.grad());
+            assert_eq!(b1.grad(), b2.grad());
+        };
+
+        check(|a, b| a + b, |a, b| a + b);
+        check(|a, b| a - b, |a, b| a - b);
+        check(|a, b| a * b, |a, b| a * b);
+        check(|a, b| a / b, |a, b| a / b);
+
+        check(|_, b| 2.0 + b, |_, b| 2.0 + b);
+        check(|_, b| 2.0 - b, |_, b| 2.0 - b);
+        check(|_, b| 2.0 * b, |_, b| 2.0 * b);
+        check(|_, b| 2.0 / b, |_, b| 2.0 / b);
+    }
+
+    #[test]
+    fn test_value_from() {
+        let a = Value::from(2.5_f32);
+        let b: Value32 = 3.into();
+        let c: Vec<Value32> = vec![1.0, 2.0].into_iter().map(Value::from).collect();
+        let d = Value::from_slice(&[1.0_f32, 2.0, 3.0]);
+
+        assert_eq!(a.data(), 2.5);
+        assert_eq!(b.data(), 3.0);
+        assert_eq!(
+            c.iter().map(|v| v.data()).collect::<Vec<_>>(),
+            vec![1.0, 2.0]
+        );
+        assert_eq!(
+            d.iter().map(|v| v.data()).collect::<Vec<_>>(),
+            vec![1.0, 2.0, 3.0]
+        );
+        assert!([a, b]
+            .iter()
+            .chain(&c)
+            .chain(&d)
+            .all(|v| v.op().is_empty() && v.grad() == 0.0));
+    }
+
+    #[test]
+    fn test_value_partial_ord() {
+        let mut values = Value::from_slice(&[0.5_f32, -2.0, 3.0, 1.0]);
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let sorted: Vec<f32> = values.iter().map(|v| v.data()).collect();
+        assert_eq!(sorted, vec![-2.0, 0.5, 1.0, 3.0]);
+
+        let max = values
+            .iter()
+            .max_by(|a, b| a.partial_cmp(b).unwrap())
+            .unwrap();
+        assert_eq!(*max, 3.0);
+
+        assert!(values[0] < values[1]);
+        assert!(values[3] > 2.0);
+        assert!(2.0 < values[3]);
+        assert!(Value::new(f32::NAN).partial_cmp(&values[0]).is_none());
+    }
+
+    #[test]
+    fn test_value_id() {
+        let a = Value32::new(1.0);
+        let b = Value32::new(1.0);
+        let c = &a + &b;
+
+        assert_eq!(a.id(), a.clone().id());
+        assert_ne!(a.id(), b.id());
+        assert_ne!(c.id(), a.id());
+        assert_ne!(c.id(), b.id());
+        assert_eq!(c.prev()[0].id(), a.id());
+    }
+
+    #[test]
+    // the hash only reads the id, which never changes after construction
+    #[allow(clippy::mutable_key_type)]
+    fn test_value_by_identity() {
+        let a = Value32::new(1.0);
+        let b = Value32::new(1.0);
+
+        let mut set = HashSet::new();
+        assert!(set.insert(ByIdentity(a.clone())));
+        assert!(!set.insert(ByIdentity(a.clone())));
+        assert!(set.insert(ByIdentity(b.clone())));
+
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&ByIdentity(b)));
+
+        // data-based equality is unchanged
+        assert_eq!(a, Value32::new(1.0));
+    }
+
+    #[test]
+    fn test_value_recip() {
+        for x in [-3.0, -0.5, 0.25, 2.0] {
+            let a = &Value64::new(x);
+            let b = &Value64::new(x);
+
+            let recip = a.recip();
+            let pow = b.powf(-1.0);
+            recip.backward();
+            pow.backward();
+
+            assert_eq!(recip.op(), String::from("recip"));
+            assert!((recip.data() - pow.data()).abs() < 1e-12);
+            assert!(
+                (a.grad() - b.grad()).abs() < 1e-12,
+                "testing Value recip backward on {}",
+                x
+            );
+            assert_eq!(recip.topo().len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_value_grad_disabled() {
+        let forward = |a: &Value32, b: &Value32| (&(a * b) + a).tanh().powf(2.0);
+
+        let a = &Value32::new(0.5);
+        let b = &Value32::new(-1.5);
+        let with_grad = forward(a, b);
+
+        set_grad_enabled(false);
+        let without_grad = forward(a, b);
+        let custom = Value32::with_op(1.0, Some(vec![a.clone()]), "custom");
+        custom.set_backward(|| panic!("never installed"));
+        set_grad_enabled(true);
+
+        assert!(is_grad_enabled());
+        assert_eq!(with_grad.data(), without_grad.data());
+        assert_eq!(without_grad.op(), String::from("pow"));
+        assert!(without_grad.prev().is_empty());
+        assert_eq!(without_grad.graph_size(), 1);
+
+        // nothing to propagate, and the custom closure was never stored
+        without_grad.backward();
+        custom.backward();
+        assert_eq!(a.grad(), 0.0);
+        assert!(!with_grad.prev().is_empty());
+    }
+
+    #[test]
+    fn test_value_no_grad_guard() {
+        let a = &Value32::new(0.5);
+
+        {
+            let _outer = no_grad();
+            let inside = a.exp();
+            assert!(inside.prev().is_empty());
+
+            {
+                let _inner = no_grad();
+                assert!(!is_grad_enabled());
+            }
+
+            // the inner guard restores the disabled state it found
+            assert!(!is_grad_enabled());
+            assert_eq!(inside.data(), 0.5_f32.exp());
+        }
+
+        assert!(is_grad_enabled());
+        assert_eq!(a.exp().prev().len(), 1);
+    }
+
     #[test]
     fn test_value_mul() {
         let a = &Value::new(5.0);
@@ -485,6 +1708,143 @@ mod tests {
         assert_eq!(a.grad(), 4.0);
     }
 
+    #[test]
+    fn test_value_powv_backward() {
+        let f = |x: f32, p: f32| x.powf(p);
+        let eps = 1e-3;
+
+        let x = &Value::new(1.5);
+        let p = &Value::new(2.5);
+
+        let result = x.powv(p);
+        result.backward();
+
+        let dx = (f(1.5 + eps, 2.5) - f(1.5 - eps, 2.5)) / (2.0 * eps);
+        let dp = (f(1.5, 2.5 + eps) - f(1.5, 2.5 - eps)) / (2.0 * eps);
+
+        assert!(
+            (x.grad() - dx).abs() < 1e-2,
+            "base grad {} vs {}",
+            x.grad(),
+            dx
+        );
+        assert!(
+            (p.grad() - dp).abs() < 1e-2,
+            "exponent grad {} vs {}",
+            p.grad(),
+            dp
+        );
+    }
+
+    #[test]
+    fn test_value_powi() {
+        for x in [-1.5_f64, 0.5, 2.0] {
+            let a = Value::new(x);
+            let b = Value::new(x);
+
+            let by_powi = a.powi(3);
+            let by_powf = b.powf(3.0);
+            by_powi.backward();
+            by_powf.backward();
+
+            assert_eq!(by_powi.op(), String::from("powi"));
+            assert!((by_powi.data() - by_powf.data()).abs() < 1e-12);
+            assert!((a.grad() - b.grad()).abs() < 1e-12);
+            assert_eq!(a.grad(), 3.0 * x * x);
+        }
+
+        // x^0 is flat everywhere, including at zero
+        let z = Value::new(0.0_f64);
+        let one = z.powi(0);
+        one.backward();
+        assert_eq!((one.data(), z.grad()), (1.0, 0.0));
+
+        let x = Value::new(2.0_f64);
+        let second = x.powi(3).gradients(std::slice::from_ref(&x))[0]
+            .gradients(std::slice::from_ref(&x))
+            .remove(0);
+        assert_eq!(second.data(), 12.0);
+
+        assert_eq!(x.pow(&Value::new(3.0)).data(), 8.0);
+    }
+
+    #[test]
+    fn test_value_ln() {
+        let a = &Value::new(std::f32::consts::E);
+        let result = a.ln();
+
+        assert!(
+            (result.data() - 1.0).abs() < 1e-6,
+            "testing Value ln on {:?}",
+            a
+        );
+        assert_eq!(result.op(), String::from("ln"));
+        assert!(Value::new(-1.0_f32).ln().data().is_nan());
+    }
+
+    #[test]
+    fn test_value_ln_backward() {
+        let a = &Value::new(4.0);
+
+        let result = a.ln();
+        result.set_grad(2.0);
+        result.once_backward();
+
+        assert_eq!(
+            a.grad(),
+            1.0 / 4.0 * 2.0,
+            "testing Value ln backward on {:?}",
+            a
+        );
+    }
+
+    #[test]
+    fn test_value_sqrt_backward() {
+        let a = &Value::new(2.5_f32);
+        let b = &Value::new(2.5_f32);
+
+        let result = a.sqrt();
+        result.backward();
+        let expected = b.powf(0.5);
+        expected.backward();
+
+        assert_eq!(result.op(), String::from("sqrt"));
+        assert_eq!(result.prev().len(), 1);
+        assert!((result.data() - expected.data()).abs() < 1e-6);
+        assert!(
+            (a.grad() - b.grad()).abs() < 1e-6,
+            "{} vs {}",
+            a.grad(),
+            b.grad()
+        );
+    }
+
+    #[test]
+    fn test_value_sin_cos_backward() {
+        use std::f64::consts::FRAC_PI_2;
+
+        // (x, sin, d sin, cos, d cos)
+        for (x, sin, dsin, cos, dcos) in
+            [(0.0, 0.0, 1.0, 1.0, 0.0), (FRAC_PI_2, 1.0, 0.0, 0.0, -1.0)]
+        {
+            let a = &Value64::new(x);
+            let result = a.sin();
+            result.backward();
+
+            assert_eq!(result.op(), String::from("sin"));
+            assert!((result.data() - sin).abs() < 1e-12, "sin({})", x);
+            assert!((a.grad() - dsin).abs() < 1e-12, "sin'({})", x);
+
+            let a = &Value64::new(x);
+            let result = a.cos();
+            result.backward();
+
+            assert_eq!(result.op(), String::from("cos"));
+            assert!((result.data() - cos).abs() < 1e-12, "cos({})", x);
+            assert!((a.grad() - dcos).abs() < 1e-12, "cos'({})", x);
+        }
+    }
+
     #[test]
     fn test_value_tanh() {
         let a = &Value::new(2.0);
@@ -496,7 +1856,7 @@ mod tests {
 
     #[test]
     fn test_value_tanh_backward() {
-        let a = &Value::new(2.0);
+        let a = &Value32::new(2.0);
 
         let result = a.tanh();
         result.set_grad(2.0);
@@ -507,15 +1867,323 @@ mod tests {
         assert_eq!(a.grad(), expected, "testing Value tanh backward on {:?}", a);
     }
 
+    #[test]
+    fn test_value_tanh_single_node() {
+        let a = &Value64::new(0.7);
+
+        let result = a.tanh();
+        result.backward();
+
+        assert_eq!(result.data(), 0.7_f64.tanh());
+        assert_eq!(a.grad(), 1.0 - 0.7_f64.tanh().powi(2));
+
+        // no intermediate exp/div nodes between the input and the output
+        assert_eq!(result.topo().len(), 2);
+        assert_eq!(result.prev()[0].id(), a.id());
+    }
+
+    #[test]
+    fn test_value_tanh_saturates() {
+        for (input, expected) in [(100.0_f32, 1.0), (-100.0, -1.0), (50.0, 1.0)] {
+            let a = &Value::new(input);
+
+            let result = a.tanh();
+            result.backward();
+
+            assert!(result.data().is_finite());
+            assert!((result.data() - expected).abs() < 1e-6);
+            assert!(
+                a.grad().is_finite(),
+                "testing Value tanh backward on {:?}",
+                a
+            );
+        }
+    }
+
+    #[test]
+    fn test_value_tanh_backward_f64() {
+        let a = &Value64::new(2.0);
+
+        let result = a.tanh();
+        result.set_grad(2.0);
+        result.once_backward();
+
+        let expected: f64 = (1.0 - a.data().tanh().powi(2)) * result.grad();
+
+        assert!(
+            (a.grad() - expected).abs() < 1e-12,
+            "testing Value tanh backward on {:?}",
+            a
+        );
+    }
+
+    #[test]
+    fn test_activation_derivative() {
+        let x = Value::new(0.7_f64);
+
+        let derivative = activation_derivative("tanh", &x).unwrap();
+        assert_eq!(x.graph_size(), 1, "nothing is attached to the input");
+
+        x.tanh().backward();
+        assert_eq!(derivative, x.grad());
+        assert!((derivative - (1.0 - 0.7_f64.tanh().powi(2))).abs() < 1e-12);
+
+        for op in ["exp", "ln", "sigmoid", "softplus", "relu"] {
+            let x = Value::new(0.7_f64);
+            let derivative = activation_derivative(op, &x).unwrap();
+
+            match op {
+                "exp" => x.exp(),
+                "ln" => x.ln(),
+                "sigmoid" => x.sigmoid(),
+                "softplus" => x.softplus(),
+                _ => x.relu(),
+            }
+            .backward();
+
+            assert_eq!(derivative, x.grad(), "{}", op);
+        }
+
+        assert_eq!(
+            activation_derivative("relu", &Value::new(-1.0_f32)),
+            Some(0.0)
+        );
+        assert_eq!(activation_derivative("max", &Value::new(1.0_f32)), None);
+    }
+
+    #[test]
+    fn test_value_sigmoid_backward() {
+        let a = &Value::new(2.0);
+
+        let result = a.sigmoid();
+        result.set_grad(2.0);
+        result.once_backward();
+
+        let s: f32 = 1.0 / (1.0 + (-2.0_f32).exp());
+        let expected: f32 = s * (1.0 - s) * result.grad();
+
+        assert_eq!(result.op(), String::from("sigmoid"));
+        assert_eq!(
+            a.grad(),
+            expected,
+            "testing Value sigmoid backward on {:?}",
+            a
+        );
+    }
+
+    #[test]
+    fn test_value_zero_grad() {
+        let a = &Value::new(2.0);
+        let result = a * a;
+        result.backward();
+
+        assert_eq!(a.grad(), 4.0);
+
+        a.zero_grad();
+
+        assert_eq!(a.grad(), 0.0);
+    }
+
+    #[test]
+    fn test_value_sgd_step() {
+        let a = &Value::new(2.0_f64);
+        let result = a * a;
+        result.backward();
+
+        // grad is 4, so the step goes down by 0.1 * 4
+        a.sgd_step(0.1);
+        assert!((a.data() - 1.6).abs() < 1e-12);
+        assert_eq!(a.grad(), 4.0);
+
+        let b = &Value::new(1.0);
+        (-b).backward();
+        b.sgd_step(0.5);
+        assert_eq!(b.data(), 1.5);
+
+        b.apply_update(-0.25);
+        assert_eq!(b.data(), 1.25);
+    }
+
+    #[test]
+    fn test_value_relu() {
+        let a = &Value::new(2.0);
+        let b = &Value::new(-2.0);
+
+        assert_eq!(a.relu().data(), 2.0, "testing Value relu on {:?}", a);
+        assert_eq!(b.relu().data(), 0.0, "testing Value relu on {:?}", b);
+        assert_eq!(a.relu().op(), String::from("relu"));
+    }
+
+    #[test]
+    fn test_value_relu_backward() {
+        let a = &Value::new(2.0);
+
+        let result = a.relu();
+        result.set_grad(2.0);
+        result.once_backward();
+
+        assert_eq!(a.grad(), 2.0, "testing Value relu backward on {:?}", a);
+
+        let b = &Value::new(-2.0);
+
+        let result = b.relu();
+        result.set_grad(2.0);
+        result.once_backward();
+
+        assert_eq!(b.grad(), 0.0, "testing Value relu backward on {:?}", b);
+    }
+
+    #[test]
+    fn test_value_leaky_relu_backward() {
+        for (input, data, grad) in [(2.0_f32, 2.0, 3.0), (-2.0, -0.02, 0.03)] {
+            let a = &Value::new(input);
+
+            let result = a.leaky_relu(0.01);
+            result.set_grad(3.0);
+            result.once_backward();
+
+            assert_eq!(result.data(), data, "testing Value leaky_relu on {:?}", a);
+            assert_eq!(result.op(), String::from("leaky_relu"));
+            assert_eq!(
+                a.grad(),
+                grad,
+                "testing Value leaky_relu backward on {:?}",
+                a
+            );
+        }
+    }
+
+    #[test]
+    fn test_value_elu() {
+        let a = &Value::new(2.0_f32);
+        let b = &Value::new(-2.0_f32);
+
+        assert_eq!(a.elu(0.5).data(), 2.0);
+        assert_eq!(b.elu(0.5).data(), 0.5 * ((-2.0_f32).exp() - 1.0));
+        assert_eq!(a.elu(0.5).op(), String::from("elu"));
+
+        // with alpha = 1 both pieces meet at 0 in value and slope
+        let eps = 1e-4;
+        let left = &Value64::new(-eps);
+        let right = &Value64::new(eps);
+        let (l, r) = (left.elu(1.0), right.elu(1.0));
+        l.backward();
+        r.backward();
+
+        assert!((l.data() - r.data()).abs() < 1e-3);
+        assert!((left.grad() - right.grad()).abs() < 1e-3);
+        assert_eq!(Value64::new(0.0).elu(1.0).data(), 0.0);
+    }
+
+    #[test]
+    fn test_value_gelu() {
+        let reference = |x: f64| {
+            0.5 * x
+                * (1.0 + ((2.0 / std::f64::consts::PI).sqrt() * (x + 0.044715 * x.powi(3))).tanh())
+        };
+
+        for x in [-3.0, -1.0, -0.1, 0.0, 0.5, 2.0] {
+            let result = Value64::new(x).gelu();
+
+            assert!(
+                (result.data() - reference(x)).abs() < 1e-12,
+                "testing Value gelu on {}",
+                x
+            );
+            assert_eq!(result.op(), String::from("gelu"));
+        }
+    }
+
+    #[test]
+    fn test_value_softplus() {
+        let a = &Value::new(50.0_f32);
+
+        let result = a.softplus();
+        result.backward();
+
+        assert!(result.data().is_finite());
+        assert_eq!(result.data(), 50.0);
+        assert_eq!(result.op(), String::from("softplus"));
+        assert_eq!(a.grad(), 1.0);
+
+        for x in [-3.0, -0.5, 0.0, 0.5, 3.0] {
+            let a = &Value64::new(x);
+
+            let result = a.softplus();
+            result.backward();
+
+            assert!((result.data() - x.exp().ln_1p()).abs() < 1e-12);
+            assert!((a.grad() - Value64::new(x).sigmoid().data()).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_value_abs_backward() {
+        for (input, data, grad) in [(2.0, 2.0, 3.0), (-2.0, 2.0, -3.0), (0.0, 0.0, 0.0)] {
+            let a = &Value::new(input);
+
+            let result = a.abs();
+            result.set_grad(3.0);
+            result.once_backward();
+
+            assert_eq!(result.data(), data, "testing Value abs on {:?}", a);
+            assert_eq!(a.grad(), grad, "testing Value abs backward on {:?}", a);
+        }
+    }
+
+    #[test]
+    fn test_value_max_backward() {
+        // (left, right, left grad, right grad)
+        for (left, right, lgrad, rgrad) in [
+            (3.0, 1.0, 2.0, 0.0),
+            (1.0, 3.0, 0.0, 2.0),
+            (2.0, 2.0, 2.0, 0.0),
+        ] {
+            let a = &Value::new(left);
+            let b = &Value::new(right);
+
+            let result = a.max(b);
+            result.set_grad(2.0);
+            result.once_backward();
+
+            assert_eq!(result.data(), f32::max(left, right));
+            assert_eq!(result.op(), String::from("max"));
+            assert_eq!(a.grad(), lgrad, "testing Value max backward on {:?}", a);
+            assert_eq!(b.grad(), rgrad, "testing Value max backward on {:?}", b);
+        }
+    }
+
+    #[test]
+    fn test_value_min_backward() {
+        // (left, right, left grad, right grad)
+        for (left, right, lgrad, rgrad) in [
+            (1.0, 3.0, 2.0, 0.0),
+            (3.0, 1.0, 0.0, 2.0),
+            (2.0, 2.0, 2.0, 0.0),
+        ] {
+            let a = &Value::new(left);
+            let b = &Value::new(right);
+
+            let result = a.min(b);
+            result.set_grad(2.0);
+            result.once_backward();
+
+            assert_eq!(result.data(), f32::min(left, right));
+            assert_eq!(result.op(), String::from("min"));
+            assert_eq!(a.grad(), lgrad, "testing Value min backward on {:?}", a);
+            assert_eq!(b.grad(), rgrad, "testing Value min backward on {:?}", b);
+        }
+    }
+
     #[test]
     fn test_value_backward() {
-        let x1 = &Value::new(2.0);
-        let x2 = &Value::new(0.0);
+        let x1 = &Value32::new(2.0);
+        let x2 = &Value32::new(0.0);
 
-        let w1 = &Value::new(-3.0);
-        let w2 = &Value::new(1.0);
+        let w1 = &Value32::new(-3.0);
+        let w2 = &Value32::new(1.0);
 
-        let b = &Value::new(6.8813735870195432);
+        let b = &Value32::new(6.8813735870195432);
 
         let ref x1w1 = x1 * w1;
         let ref x2w2 = x2 * w2;
@@ -529,6 +2197,353 @@ mod tests {
         assert_eq!(out.data(), 0.7071067);
         assert_eq!(x1w1x2w2b.grad(), 0.5000001);
         assert_eq!(x1.grad(), -1.5000004);
+
+        // 5 leaves, 2 products, 2 sums and the tanh
+        assert_eq!(out.graph_size(), 10);
+        // tanh -> +b -> + -> * -> x1
+        assert_eq!(out.graph_depth(), 4);
+        assert_eq!(x1.graph_size(), 1);
+        assert_eq!(x1.graph_depth(), 0);
+    }
+
+    #[test]
+    fn test_value_clamp_backward() {
+        // (input, clamped, grad)
+        for (input, data, grad) in [(-5.0, -1.0, 0.0), (5.0, 1.0, 0.0), (0.5, 0.5, 2.0)] {
+            let a = &Value::new(input);
+
+            let result = a.clamp(-1.0, 1.0);
+            result.set_grad(2.0);
+            result.once_backward();
+
+            assert_eq!(result.op(), String::from("clamp"));
+            assert_eq!(result.data(), data, "testing Value clamp on {:?}", a);
+            assert_eq!(a.grad(), grad, "testing Value clamp backward on {:?}", a);
+        }
+    }
+
+    #[test]
+    fn test_value_exp_clamped() {
+        let a = &Value32::new(1000.0);
+
+        assert!(a.exp().data().is_infinite());
+
+        let result = a.exp_clamped(88.0);
+        result.backward();
+
+        assert_eq!(result.op(), String::from("exp_clamped"));
+        assert_eq!(result.data(), 88.0_f32.exp());
+        assert!(result.data().is_finite());
+        assert!(a.grad().is_finite());
+
+        // inside the range it is plain exp
+        let b = &Value::new(2.0_f64);
+        let result = b.exp_clamped(88.0);
+        result.backward();
+        assert_eq!(result.data(), 2.0_f64.exp());
+        assert_eq!(b.grad(), 2.0_f64.exp());
+    }
+
+    #[test]
+    fn test_value_backward_from() {
+        let x = Value::new(0.5_f64);
+        let w = Value::new(-1.5_f64);
+        let forward = || {
+            let hidden = (&x * &w).tanh();
+            (&hidden * 3.0, hidden.exp())
+        };
+
+        // each head on its own graph, grads accumulating in x and w
+        let (first, _) = forward();
+        (&first * 2.0).backward();
+        let (_, second) = forward();
+        (&second * 0.5).backward();
+        let separate = (x.grad(), w.grad());
+
+        x.zero_grad();
+        w.zero_grad();
+        let (first, second) = forward();
+        Value::backward_from(&[(first.clone(), 2.0), (second.clone(), 0.5)]);
+
+        assert_eq!(first.grad(), 2.0);
+        assert_eq!(second.grad(), 0.5);
+        assert!((x.grad() - separate.0).abs() < 1e-12);
+        assert!((w.grad() - separate.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_value_second_derivative() {
+        let x = Value::new(3.0_f64);
+        let wrt = std::slice::from_ref(&x);
+        let y = &x * &x;
+
+        let dy = y.gradients(wrt).remove(0);
+        assert_eq!(dy.data(), 6.0);
+        assert_eq!(x.grad(), 0.0, "grads are left alone");
+
+        let d2y = dy.gradients(wrt).remove(0);
+        assert_eq!(d2y.data(), 2.0);
+
+        // backward through the gradient graph gives the same thing
+        dy.backward();
+        assert_eq!(x.grad(), 2.0);
+    }
+
+    #[test]
+    fn test_value_gradients_match_backward() {
+        let x = Value::new(0.4_f64);
+        let w = Value::new(-1.2_f64);
+        let unused = Value::new(1.0_f64);
+
+        let out = &(&(&x * &w).tanh() / &x.exp()) - &(&w.sigmoid() + &x.powf(3.0)).ln();
+        let grads = out.gradients(&[x.clone(), w.clone(), unused]);
+
+        out.backward();
+        assert!((grads[0].data() - x.grad()).abs() < 1e-12);
+        assert!((grads[1].data() - w.grad()).abs() < 1e-12);
+        assert_eq!(grads[2].data(), 0.0);
+
+        // d2/dx2 tanh(x) = -2 tanh(x) (1 - tanh(x)^2)
+        let t = x.tanh();
+        let wrt = std::slice::from_ref(&x);
+        let d2 = t.gradients(wrt)[0].gradients(wrt).remove(0);
+        let th = 0.4_f64.tanh();
+        assert!((d2.data() - -2.0 * th * (1.0 - th * th)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_value_backward_with_names() {
+        let x = Value::new(3.0_f64);
+        x.set_name("x");
+
+        let hidden = &x * 2.0;
+        hidden.set_name("hidden");
+        let out = hidden.tanh();
+
+        assert_eq!(hidden.name(), Some(String::from("hidden")));
+        assert_eq!(out.name(), None);
+
+        let grads = out.backward_with_names();
+
+        let expected = 1.0 - 6.0_f64.tanh().powi(2);
+        assert_eq!(grads.len(), 2);
+        assert_eq!(grads["hidden"], expected);
+        assert_eq!(grads["hidden"], hidden.grad());
+        assert_eq!(grads["x"], 2.0 * expected);
+    }
+
+    #[test]
+    fn test_value_check_finite() {
+        let x = Value::new(-1.0_f32);
+        let w = Value::new(2.0_f32);
+        let healthy = &(&x * &w) + 1.0;
+
+        healthy.backward();
+        assert_eq!(healthy.check_finite(), Ok(()));
+
+        let bad = x.ln();
+        let out = &(&bad * &w) + &healthy;
+
+        let err = out.check_finite().unwrap_err();
+        assert_eq!(err.id, bad.id());
+        assert_eq!(err.op, "ln");
+        assert!(err.data.is_nan());
+        assert!(err
+            .to_string()
+            .starts_with(&format!("node {} (\"ln\")", bad.id())));
+
+        // an exploding grad is caught as well
+        w.set_grad(f32::INFINITY);
+        assert_eq!(healthy.check_finite().unwrap_err().id, w.id());
+    }
+
+    #[test]
+    fn test_value_detach() {
+        let a = &Value::new(3.0_f32);
+        let b = &Value::new(2.0);
+
+        let detached = &a.detach();
+        let out = &(a * b) + &(detached * b);
+        out.backward();
+
+        assert_eq!(detached.data(), 3.0);
+        assert!(detached.prev().is_empty());
+        assert_eq!(detached.grad(), 2.0);
+        assert_eq!(a.grad(), 2.0, "testing only the attached path reaches a");
+
+        let c = &Value::new(3.0_f32);
+        let out = &c.detach() * &c.detach();
+        out.backward();
+
+        assert_eq!(c.grad(), 0.0);
+    }
+
+    #[test]
+    fn test_value_build_topo() {
+        let a = &Value::new(2.0);
+        let b = &Value::new(3.0);
+        let ab = &(a * b);
+        let out = &(ab + a);
+
+        let mut visited = vec![];
+        let topo = out.build_topo(&mut visited, &mut vec![]);
+
+        assert_eq!(topo.len(), 4, "testing shared nodes are only visited once");
+        assert_eq!(visited.len(), 4);
+        assert!(a.is_in(&topo[..2].to_vec()) && b.is_in(&topo[..2].to_vec()));
+        assert!(ab.is_in(&topo[2..3].to_vec()));
+        assert!(out.is_in(&topo[3..].to_vec()));
+
+        // already visited nodes are skipped along with everything below them
+        let mut visited = vec![ab.clone()];
+        let topo = out.build_topo(&mut visited, &mut vec![]);
+        assert_eq!(topo.len(), 2);
+        assert!(a.is_in(&topo[..1].to_vec()) && out.is_in(&topo[1..].to_vec()));
+    }
+
+    #[test]
+    fn test_value_build_topo_matches_recursive() {
+        fn recursive(value: &Value, visited: &mut Vec<Value>, topo: &mut Vec<Value>) {
+            if !value.is_in(visited) {
+                visited.push(value.clone());
+
+                for child in value.prev() {
+                    recursive(&child, visited, topo);
+                }
+
+                topo.push(value.clone());
+            }
+        }
+
+        let x1 = &Value::new(2.0);
+        let x2 = &Value::new(0.0);
+        let w1 = &Value::new(-3.0);
+        let w2 = &Value::new(1.0);
+        let b = &Value::new(6.881_373_6);
+
+        let out = ((x1 * w1) + (x2 * w2) + b.clone()).tanh();
+
+        let mut expected = Vec::new();
+        recursive(&out, &mut Vec::new(), &mut expected);
+        let topo = out.topo();
+
+        assert_eq!(topo.len(), expected.len());
+        topo.iter()
+            .zip(expected.iter())
+            .for_each(|(t, e)| assert!(t.is_in(&vec![e.clone()])));
+    }
+
+    #[test]
+    fn test_value_backward_deep_graph() {
+        let x = &Value::new(1.0);
+
+        let mut out = x.clone();
+        for _ in 0..100_000 {
+            out = &out + x;
+        }
+        out.backward();
+
+        assert_eq!(out.data(), 100_001.0);
+        assert_eq!(x.grad(), 100_001.0);
+    }
+
+    #[test]
+    fn test_value_backward_large_graph() {
+        let xs: Vec<Value> = (0..1000).map(|i| Value::new(i as f32 / 1000.0)).collect();
+        let w = &Value::new(0.5);
+
+        let out: Value = xs.iter().map(|x| x * w).sum();
+        out.backward();
+
+        assert_eq!(out.topo().len(), 3 * xs.len() + 2);
+        assert!(xs.iter().all(|x| x.grad() == 0.5));
+        assert!((w.grad() - 499.5).abs() < 1e-2, "w grad {}", w.grad());
+    }
+
+    #[test]
+    fn test_value_graph_is_released() {
+        let a = &Value::new(2.0);
+        let b = &Value::new(-3.0);
+
+        let out = (&(a * b) + a).tanh();
+        let weak = out.downgrade();
+        out.backward();
+
+        assert!(Shared::strong_count(&a.0) > 1);
+
+        drop(out);
+
+        assert!(weak.upgrade().is_none(), "testing output node is freed");
+        assert_eq!(Shared::strong_count(&a.0), 1);
+        assert_eq!(Shared::strong_count(&b.0), 1);
+        assert_ne!(a.grad(), 0.0, "testing leaf grads survive the release");
+    }
+
+    #[cfg(feature = "threadsafe")]
+    #[test]
+    fn test_value_backward_on_spawned_thread() {
+        let a = Value32::new(2.0);
+        let b = Value32::new(-3.0);
+
+        let (a2, b2) = (a.clone(), b.clone());
+        let out = std::thread::spawn(move || {
+            let out = (&(&a2 * &b2) + &a2).tanh();
+            out.backward();
+
+            out
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(out.data(), (-4.0_f32).tanh());
+        assert_eq!(a.grad(), (1.0 - out.data().powi(2)) * (b.data() + 1.0));
+        assert_eq!(b.grad(), (1.0 - out.data().powi(2)) * a.data());
+
+        // per-sample forward passes on worker threads, summed on this one
+        let mlp = crate::mlp::Mlp::<f32>::new(3, vec![4, 1]);
+        let xs = [
+            vec![2.0, 3.0, -1.0],
+            vec![3.0, -1.0, 0.5],
+            vec![0.5, 1.0, 1.0],
+        ];
+        let preds: Vec<Value32> = std::thread::scope(|scope| {
+            let handles: Vec<_> = xs
+                .iter()
+                .map(|x| scope.spawn(|| mlp.callf(x)[0].clone()))
+                .collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let loss: Value32 = preds.into_iter().sum();
+        mlp.zero_grad();
+        loss.backward();
+
+        assert!(mlp.parameters().iter().any(|p| p.grad() != 0.0));
+    }
+
+    #[test]
+    fn test_value_to_dot() {
+        let x1 = &Value::new(2.0);
+        let x2 = &Value::new(0.0);
+        let w1 = &Value::new(-3.0);
+        let w2 = &Value::new(1.0);
+        let b = &Value::new(6.881_373_6);
+
+        let out = ((x1 * w1) + (x2 * w2) + b.clone()).tanh();
+        out.backward();
+
+        let dot = out.to_dot();
+
+        let topo = out.topo();
+        let ops = topo.iter().filter(|v| !v.op().is_empty()).count();
+        let edges = topo.iter().map(|v| v.prev().len()).sum::<usize>() + ops;
+
+        assert!(dot.starts_with("digraph {"));
+        assert_eq!(dot.matches("shape=record").count(), topo.len());
+        assert_eq!(dot.matches("shape=circle").count(), ops);
+        assert_eq!(dot.matches("->").count(), edges);
+        assert!(dot.contains("[label=\"tanh\", shape=circle]"));
     }
 
     #[test]
@@ -1,6 +1,18 @@
 //! tests/simple_model_test.rs
 
-use micrograd_rust::{mlp::Mlp, value::Value};
+// test_manual_training_loop predates running clippy on the tests and is kept
+// as originally written
+#![allow(clippy::useless_vec, clippy::get_first)]
+
+use micrograd_rust::{
+    loss,
+    mlp::Mlp,
+    optim::{Adam, Optimizer, Sgd},
+    trainer::{Trainer, TrainerConfig},
+    value::Value,
+};
+
+const SEED: u64 = 42;
 
 #[test]
 fn test_manual_training_loop() {
@@ -14,7 +26,7 @@ fn test_manual_training_loop() {
     //
     // actually, this result might have been from not calling
     // zero_grad on the network before calling backward again
-    let mlp = Mlp::new(3, vec![4,4,1]);
+    let mlp = Mlp::new_seeded(3, vec![4,4,1], SEED);
 
     let xs = vec![
         vec![2.0, 3.0, -1.0],
@@ -33,9 +45,7 @@ fn test_manual_training_loop() {
         loss = ypred.iter().zip(ys).map(|(pred, ygt)| (pred-ygt).powf(2.0)).sum();
         
         // backward pass
-        mlp.parameters().iter().for_each(|p| {
-            p.set_grad(0.0);
-        });
+        mlp.zero_grad();
         loss.backward();
         
         // update
@@ -50,8 +60,164 @@ fn test_manual_training_loop() {
         }
     }
     
-    // may fail due to random chance
-    // which means it is not a good test
-    // but oh well
+    // used to fail every so often due to random chance,
+    // the seed keeps it deterministic
     assert!(loss.data() < 0.06);
 }
+
+#[test]
+fn test_forward_loss() {
+    let mlp = Mlp::new_seeded(3, vec![4, 4, 1], SEED);
+
+    let xs = [
+        vec![2.0, 3.0, -1.0],
+        vec![3.0, -1.0, 0.5],
+        vec![0.5, 1.0, 1.0],
+        vec![1.0, 1.0, -1.0],
+    ];
+    let ys = [1.0, -1.0, -1.0, 1.0];
+
+    // the forward pass of test_manual_training_loop
+    let ypred: Vec<Value> = xs.iter().map(|input| mlp.callf(input)[0].clone()).collect();
+    let manual: Value = ypred.iter().zip(ys).map(|(pred, ygt)| (pred - ygt).powf(2.0)).sum();
+
+    let loss = mlp.forward_loss(&xs, &ys, loss::mse);
+    assert_eq!(loss.data(), manual.data());
+
+    loss.backward();
+    assert!(mlp.parameters().iter().any(|p| p.grad() != 0.0));
+}
+
+#[test]
+fn test_adam_training_loop() {
+    let xs = [
+        vec![2.0, 3.0, -1.0],
+        vec![3.0, -1.0, 0.5],
+        vec![0.5, 1.0, 1.0],
+        vec![1.0, 1.0, -1.0],
+    ];
+    let ys = [1.0, -1.0, -1.0, 1.0];
+
+    // two networks starting from the same weights
+    let manual = Mlp::new_seeded(3, vec![4, 4, 1], SEED);
+    let adaptive = Mlp::new_seeded(3, vec![4, 4, 1], SEED);
+
+    let loss_of = |mlp: &Mlp| -> Value {
+        xs.iter()
+            .map(|input| mlp.callf(input)[0].clone())
+            .zip(ys)
+            .map(|(pred, ygt)| (&pred - ygt).powf(2.0))
+            .sum()
+    };
+
+    let mut manual_iterations = 0;
+    for i in 1..=200 {
+        manual_iterations = i;
+
+        let loss = loss_of(&manual);
+        manual.zero_grad();
+        loss.backward();
+        manual.parameters().iter().for_each(|p| {
+            p.set_data(p.data() + -0.06 * p.grad());
+        });
+
+        if loss_of(&manual).data() < 0.06 {
+            break;
+        }
+    }
+
+    let adam = Adam::new(adaptive.parameters(), 0.1);
+    let mut adam_iterations = 0;
+    for i in 1..=200 {
+        adam_iterations = i;
+
+        let loss = loss_of(&adaptive);
+        adam.zero_grad();
+        loss.backward();
+        adam.step();
+
+        if loss_of(&adaptive).data() < 0.06 {
+            break;
+        }
+    }
+
+    assert!(loss_of(&adaptive).data() < 0.06);
+    assert!(
+        adam_iterations < manual_iterations,
+        "adam took {} iterations, manual sgd {}",
+        adam_iterations,
+        manual_iterations
+    );
+}
+
+#[test]
+fn test_trainer_fit() {
+    let xs = [
+        vec![2.0, 3.0, -1.0],
+        vec![3.0, -1.0, 0.5],
+        vec![0.5, 1.0, 1.0],
+        vec![1.0, 1.0, -1.0],
+    ];
+    let ys = [1.0, -1.0, -1.0, 1.0];
+
+    // the same loop as test_manual_training_loop, written out by hand
+    let manual = Mlp::new_seeded(3, vec![4, 4, 1], SEED);
+    let mut manual_history = vec![];
+    for _ in 0..30 {
+        let ypred: Vec<Value> = xs.iter().map(|input| manual.callf(input)[0].clone()).collect();
+        let loss = loss::mse(&ypred, &ys);
+
+        manual.zero_grad();
+        loss.backward();
+        manual.parameters().iter().for_each(|p| {
+            p.set_data(p.data() + -0.06 * p.grad());
+        });
+
+        manual_history.push(loss.data());
+        if loss.data() < 0.06 {
+            break;
+        }
+    }
+
+    let mlp = Mlp::new_seeded(3, vec![4, 4, 1], SEED);
+    let sgd = Sgd::new(mlp.parameters(), 0.06);
+    let config = TrainerConfig {
+        early_stopping: Some(0.06),
+        ..TrainerConfig::default()
+    };
+    let mut trainer = Trainer::new_with_config(mlp, sgd, loss::mse, config);
+
+    let history = trainer.fit(&xs, &ys, 30);
+
+    assert_eq!(history, manual_history);
+    assert!(*history.last().unwrap() < 0.06);
+}
+
+#[test]
+fn test_sgd_step_matches_manual_update() {
+    let xs = [
+        vec![2.0, 3.0, -1.0],
+        vec![3.0, -1.0, 0.5],
+        vec![0.5, 1.0, 1.0],
+        vec![1.0, 1.0, -1.0],
+    ];
+    let ys = [1.0, -1.0, -1.0, 1.0];
+
+    let manual = Mlp::new_seeded(3, vec![4, 4, 1], SEED);
+    let stepped = Mlp::new_seeded(3, vec![4, 4, 1], SEED);
+
+    for _ in 0..10 {
+        for mlp in [&manual, &stepped] {
+            let loss = mlp.forward_loss(&xs, &ys, loss::mse);
+            mlp.zero_grad();
+            loss.backward();
+        }
+
+        manual.parameters().iter().for_each(|p| {
+            p.set_data(p.data() - 0.06 * p.grad());
+        });
+        stepped.parameters().iter().for_each(|p| p.sgd_step(0.06));
+    }
+
+    assert_eq!(stepped.get_parameters_flat(), manual.get_parameters_flat());
+}